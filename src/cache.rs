@@ -0,0 +1,156 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use crate::models::DetectedGame;
+
+pub const LIBRARY_CACHE_FILE: &str = "library_cache.json";
+
+/// Bumped whenever the cached data changes shape or meaning, so old caches
+/// are rescanned instead of misread.
+const CACHE_VERSION: u32 = 3;
+
+/// The state of one detector input when the detector last ran.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SourceStamp {
+    pub path: PathBuf,
+    /// Seconds and nanoseconds since the Unix epoch. `None` if the path did
+    /// not exist, so that creating it invalidates the cache as well.
+    pub modified: Option<(u64, u32)>,
+    pub size: Option<u64>,
+}
+
+pub type Fingerprint = Vec<SourceStamp>;
+
+/// Stamps every source path. Directories count too: their mtime changes
+/// when entries are added or removed.
+pub fn fingerprint(sources: &[PathBuf]) -> Fingerprint {
+    sources
+        .iter()
+        .map(|path| {
+            let metadata = std::fs::metadata(path).ok();
+            let modified = metadata
+                .as_ref()
+                .and_then(|m| m.modified().ok())
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|since| (since.as_secs(), since.subsec_nanos()));
+            SourceStamp { path: path.clone(), modified, size: metadata.map(|m| m.len()) }
+        })
+        .collect()
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct CacheEntry {
+    detector: String,
+    /// [`ScanContext::label`](crate::detectors::context::ScanContext::label)
+    /// of the machine or prefix the detector scanned.
+    scope: String,
+    fingerprint: Fingerprint,
+    games: Vec<DetectedGame>,
+}
+
+/// Detector results from earlier runs, reused while their sources are
+/// unchanged.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LibraryCache {
+    version: u32,
+    entries: Vec<CacheEntry>,
+}
+
+impl Default for LibraryCache {
+    fn default() -> Self {
+        Self { version: CACHE_VERSION, entries: Vec::new() }
+    }
+}
+
+impl LibraryCache {
+    /// Reads a saved cache. A missing, unreadable or outdated file gives an
+    /// empty cache, which just means a full scan.
+    pub fn load(path: &Path) -> Self {
+        let Ok(content) = std::fs::read_to_string(path) else {
+            return Self::default();
+        };
+
+        match serde_json::from_str::<Self>(&content) {
+            Ok(cache) if cache.version == CACHE_VERSION => cache,
+            Ok(_) => Self::default(),
+            Err(e) => {
+                eprintln!("⚠️ Ignoring library cache {}: {}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Writes the cache through a temporary file, so an interrupted save
+    /// never leaves a truncated cache behind.
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let temp_path = path.with_extension("json.tmp");
+        std::fs::write(&temp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&temp_path, path)?;
+        Ok(())
+    }
+
+    /// The games `detector` found in `scope`, if its sources still match
+    /// `fingerprint`.
+    pub fn get(&self, detector: &str, scope: &str, fingerprint: &Fingerprint) -> Option<&[DetectedGame]> {
+        self.entries
+            .iter()
+            .find(|e| e.detector == detector && e.scope == scope && e.fingerprint == *fingerprint)
+            .map(|e| e.games.as_slice())
+    }
+
+    pub fn insert(&mut self, detector: &str, scope: &str, fingerprint: Fingerprint, games: Vec<DetectedGame>) {
+        self.entries.retain(|e| e.detector != detector || e.scope != scope);
+        self.entries.push(CacheEntry {
+            detector: detector.to_string(),
+            scope: scope.to_string(),
+            fingerprint,
+            games,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::DetectionConfidence;
+
+    fn game(title: &str) -> DetectedGame {
+        DetectedGame {
+            source: "steam".to_string(),
+            store_id: Some("70".to_string()),
+            title: title.to_string(),
+            install_dir: None,
+            executables: vec![PathBuf::from("/games/hl.exe")],
+            confidence: DetectionConfidence::High,
+        }
+    }
+
+    #[test]
+    fn invalidates_entries_when_sources_change() {
+        let dir = std::env::temp_dir().join(format!("library_cache_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let manifest = dir.join("appmanifest_70.acf");
+        let missing = dir.join("libraryfolders.vdf");
+        std::fs::write(&manifest, "\"AppState\" {}").unwrap();
+
+        let sources = vec![manifest.clone(), missing.clone()];
+        let mut cache = LibraryCache::default();
+        cache.insert("steam", "live", fingerprint(&sources), vec![game("Half-Life")]);
+        assert_eq!(cache.get("steam", "live", &fingerprint(&sources)).map(|g| g.len()), Some(1));
+        assert!(cache.get("steam", "/mnt/windows", &fingerprint(&sources)).is_none());
+
+        let cache_path = dir.join(LIBRARY_CACHE_FILE);
+        cache.save(&cache_path).unwrap();
+        let loaded = LibraryCache::load(&cache_path);
+        assert_eq!(loaded.get("steam", "live", &fingerprint(&sources)).unwrap()[0].title, "Half-Life");
+
+        std::fs::write(&manifest, "\"AppState\" { \"appid\" \"70\" }").unwrap();
+        assert!(loaded.get("steam", "live", &fingerprint(&sources)).is_none());
+
+        std::fs::write(&manifest, "\"AppState\" {}").unwrap();
+        std::fs::write(&missing, "").unwrap();
+        let stale = fingerprint(&sources);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(loaded.get("steam", "live", &stale).is_none());
+    }
+}
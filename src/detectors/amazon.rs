@@ -1,16 +1,55 @@
-use rusqlite::Connection;
 use std::collections::HashSet;
 use std::path::{Path, PathBuf};
+use crate::detectors::context::ScanContext;
+use crate::detectors::{exe_selection, join_relative, GameDetector, Platform};
+use crate::models::{DetectedGame, DetectionConfidence};
+use crate::registry::RootKey;
 
-pub fn discover_amazon_games() -> HashSet<String> {
-    let mut games = HashSet::new();
+pub struct AmazonDetector;
 
-    let db_paths = get_all_amazon_db_paths();
+impl GameDetector for AmazonDetector {
+    fn id(&self) -> &'static str {
+        "amazon"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Amazon"
+    }
+
+    fn platforms(&self) -> &'static [Platform] {
+        &[Platform::Windows]
+    }
+
+    fn sources(&self, context: &ScanContext) -> Vec<PathBuf> {
+        let mut sources = context.registry_files().to_vec();
+        for db_path in get_all_amazon_db_paths(context) {
+            // The app writes through SQLite's write-ahead log, so changes
+            // reach the database file itself only at checkpoints.
+            sources.push(db_path.with_extension("sqlite-wal"));
+            sources.push(db_path);
+        }
+        sources.extend(get_fuel_path(context));
+        sources
+    }
+
+    fn discover(&self, context: &ScanContext) -> Result<Vec<DetectedGame>, Box<dyn std::error::Error>> {
+        Ok(discover_amazon_games(context))
+    }
+}
+
+pub fn discover_amazon_games(context: &ScanContext) -> Vec<DetectedGame> {
+    let mut games: Vec<DetectedGame> = Vec::new();
+
+    let db_paths = get_all_amazon_db_paths(context);
 
     for db_path in db_paths {
-        if db_path.exists() {
-            if let Ok(games_list) = parse_amazon_database(&db_path) {
-                games.extend(games_list);
+        if db_path.exists()
+            && let Ok(games_list) = parse_amazon_database(context, &db_path)
+        {
+            for game in games_list {
+                if !games.iter().any(|g| g.store_id == game.store_id) {
+                    games.push(game);
+                }
             }
         }
     }
@@ -19,16 +58,16 @@ pub fn discover_amazon_games() -> HashSet<String> {
     games
 }
 
-fn get_all_amazon_db_paths() -> Vec<PathBuf> {
+fn get_all_amazon_db_paths(context: &ScanContext) -> Vec<PathBuf> {
     let mut paths = Vec::new();
 
-    if let Some(path) = get_amazon_path() {
-        paths.push(PathBuf::from(path).join("GameInstallInfo.sqlite"));
+    if let Some(path) = get_amazon_path(context) {
+        paths.push(path.join("GameInstallInfo.sqlite"));
     }
 
-    if let Ok(localappdata) = std::env::var("LOCALAPPDATA") {
+    if let Some(localappdata) = context.env_path("LOCALAPPDATA") {
         paths.push(
-            PathBuf::from(localappdata)
+            localappdata
                 .join("Amazon Games")
                 .join("Data")
                 .join("Games")
@@ -37,10 +76,10 @@ fn get_all_amazon_db_paths() -> Vec<PathBuf> {
         );
     }
 
-    if let Some(fuel_paths) = get_fuel_library_paths() {
+    if let Some(fuel_paths) = get_fuel_library_paths(context) {
         for fuel_path in fuel_paths {
             paths.push(
-                PathBuf::from(fuel_path)
+                context.resolve(fuel_path)
                     .join("Data")
                     .join("Games")
                     .join("Sql")
@@ -52,15 +91,14 @@ fn get_all_amazon_db_paths() -> Vec<PathBuf> {
     paths.into_iter().collect::<HashSet<_>>().into_iter().collect()
 }
 
-fn get_fuel_library_paths() -> Option<Vec<String>> {
-    use serde_json::Value;
+fn get_fuel_path(context: &ScanContext) -> Option<PathBuf> {
+    Some(context.env_path("LOCALAPPDATA")?.join("Amazon Games").join("Data").join("fuel.json"))
+}
 
-    let localappdata = std::env::var("LOCALAPPDATA").ok()?;
-    let fuel_path = PathBuf::from(localappdata)
-        .join("Amazon Games")
-        .join("Data")
-        .join("fuel.json");
+fn get_fuel_library_paths(context: &ScanContext) -> Option<Vec<String>> {
+    use serde_json::Value;
 
+    let fuel_path = get_fuel_path(context)?;
     let content = std::fs::read_to_string(fuel_path).ok()?;
     let json: Value = serde_json::from_str(&content).ok()?;
 
@@ -74,54 +112,35 @@ fn get_fuel_library_paths() -> Option<Vec<String>> {
     Some(paths)
 }
 
-fn get_amazon_path() -> Option<String> {
-    use winreg::RegKey;
-    use winreg::enums::*;
-
-    let hkcu = RegKey::predef(HKEY_CURRENT_USER);
-    if let Ok(amazon_key) = hkcu.open_subkey(r"SOFTWARE\Amazon Games") {
-        if let Ok(install_path) = amazon_key.get_value::<String, _>("InstallPath") {
-            return Some(format!(r"{}\Data\Games\Sql", install_path));
-        }
-    }
-
-    None
+fn get_amazon_path(context: &ScanContext) -> Option<PathBuf> {
+    let install_path = context.registry().string(RootKey::CurrentUser, r"SOFTWARE\Amazon Games", "InstallPath")?;
+    Some(join_relative(&context.resolve(install_path), r"Data\Games\Sql"))
 }
 
-fn parse_amazon_database(db_path: &Path) -> Result<HashSet<String>, Box<dyn std::error::Error>> {
+fn parse_amazon_database(context: &ScanContext, db_path: &Path) -> Result<Vec<DetectedGame>, Box<dyn std::error::Error>> {
     use rusqlite::Connection;
 
     let conn = Connection::open(db_path)?;
-    let mut stmt = conn.prepare("SELECT InstallDirectory FROM DbSet WHERE Installed = 1")?;
+    let mut stmt = conn.prepare("SELECT Id, ProductTitle, InstallDirectory FROM DbSet WHERE Installed = 1")?;
 
-    let games: HashSet<String> = stmt
+    let games: Vec<DetectedGame> = stmt
         .query_map([], |row| {
-            let install_dir: String = row.get(0)?;
-            Ok(find_main_exe(&install_dir))
+            let id: String = row.get(0)?;
+            let title: Option<String> = row.get(1)?;
+            let install_dir: String = row.get(2)?;
+            let install_dir = context.resolve(install_dir);
+            let exe_path = exe_selection::best_executable(&install_dir, title.as_deref());
+            Ok(exe_path.map(|exe_path| DetectedGame {
+                source: "amazon".to_string(),
+                title: title.unwrap_or_else(|| id.clone()),
+                store_id: Some(id),
+                install_dir: Some(install_dir),
+                executables: vec![exe_path],
+                confidence: DetectionConfidence::Medium,
+            }))
         })?
         .filter_map(|r| r.ok().flatten())
         .collect();
 
     Ok(games)
 }
-
-fn find_main_exe(game_dir: &str) -> Option<String> {
-    std::fs::read_dir(game_dir)
-        .ok()?
-        .flatten()
-        .filter(|e| e.path().extension().and_then(|s| s.to_str()) == Some("exe"))
-        .max_by_key(|e| e.metadata().map(|m| m.len()).unwrap_or(0))
-        .map(|e| e.file_name().to_string_lossy().to_string())
-}
-
-#[cfg(target_os = "windows")]
-fn get_amazon_db_path() -> Option<PathBuf> {
-    std::env::var("LOCALAPPDATA").ok().map(|local_app_data| {
-        Path::new(&local_app_data)
-            .join("Amazon Games")
-            .join("Data")
-            .join("Games")
-            .join("Sql")
-            .join("GameInstallInfo.sqlite")
-    })
-}
@@ -0,0 +1,240 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use crate::detectors::context::ScanContext;
+use crate::detectors::{join_relative, GameDetector, Platform};
+use crate::models::{DetectedGame, DetectionConfidence};
+use crate::parsers::protobuf::Message;
+
+pub struct BattlenetDetector;
+
+impl GameDetector for BattlenetDetector {
+    fn id(&self) -> &'static str {
+        "battlenet"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Battle.net"
+    }
+
+    fn platforms(&self) -> &'static [Platform] {
+        &[Platform::Windows]
+    }
+
+    fn sources(&self, context: &ScanContext) -> Vec<PathBuf> {
+        vec![get_product_db_path(context), products_config_path()]
+    }
+
+    fn discover(&self, context: &ScanContext) -> Result<Vec<DetectedGame>, Box<dyn std::error::Error>> {
+        Ok(discover_battlenet_games(context))
+    }
+}
+
+/// Optional JSON file, next to our executable, that adds or replaces
+/// entries of the product table: `{ "fenris": { "title": "...", "executable": "..." } }`.
+const PRODUCTS_CONFIG_FILE: &str = "battlenet_products.json";
+
+/// Known Battle.net product codes, with the game's executable relative to
+/// the install path.
+const KNOWN_PRODUCTS: &[(&str, &str, &str)] = &[
+    ("wow", "World of Warcraft", r"_retail_\Wow.exe"),
+    ("wow_classic", "World of Warcraft Classic", r"_classic_\WowClassic.exe"),
+    ("wow_classic_era", "World of Warcraft Classic Era", r"_classic_era_\WowClassic.exe"),
+    ("fenris", "Diablo IV", "Diablo IV.exe"),
+    ("osi", "Diablo II: Resurrected", "D2R.exe"),
+    ("d3", "Diablo III", r"x64\Diablo III64.exe"),
+    ("anbs", "Diablo Immortal", "DiabloImmortal.exe"),
+    ("pro", "Overwatch 2", r"_retail_\Overwatch.exe"),
+    ("hs_beta", "Hearthstone", "Hearthstone.exe"),
+    ("hero", "Heroes of the Storm", r"Versions\HeroesOfTheStorm_x64.exe"),
+    ("s2", "StarCraft II", r"Versions\SC2_x64.exe"),
+    ("s1", "StarCraft: Remastered", r"x86_64\StarCraft.exe"),
+    ("w3", "Warcraft III: Reforged", r"_retail_\x86_64\Warcraft III.exe"),
+    ("rtro", "Blizzard Arcade Collection", "Blizzard Arcade Collection.exe"),
+    ("wlby", "Crash Bandicoot 4", "CrashBandicoot4.exe"),
+    ("viper", "Call of Duty: Black Ops 4", "BlackOps4.exe"),
+    ("odin", "Call of Duty: Modern Warfare", "ModernWarfare.exe"),
+    ("zeus", "Call of Duty: Black Ops Cold War", "BlackOpsColdWar.exe"),
+    ("fore", "Call of Duty: Vanguard", "Vanguard.exe"),
+    ("auks", "Call of Duty", "cod.exe"),
+];
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct ProductInfo {
+    pub title: String,
+    pub executable: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BattlenetInstall {
+    pub uid: String,
+    pub product_code: String,
+    pub install_path: PathBuf,
+    pub installed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BattlenetGame {
+    pub product_code: String,
+    pub title: String,
+    pub install_path: PathBuf,
+    pub executable: PathBuf,
+}
+
+pub fn discover_battlenet_games(context: &ScanContext) -> Vec<DetectedGame> {
+    let mut games = Vec::new();
+
+    let db_path = get_product_db_path(context);
+    if db_path.exists() {
+        let products = load_product_table(&products_config_path());
+        match std::fs::read(&db_path) {
+            Ok(data) => match parse_product_db(&data) {
+                Ok(mut installs) => {
+                    for install in &mut installs {
+                        install.install_path = context.resolve(&install.install_path);
+                    }
+                    games.extend(resolve_games(&installs, &products).into_iter().map(|game| DetectedGame {
+                        source: "battlenet".to_string(),
+                        store_id: Some(game.product_code),
+                        title: game.title,
+                        install_dir: Some(game.install_path),
+                        executables: vec![game.executable],
+                        confidence: DetectionConfidence::High,
+                    }));
+                }
+                Err(e) => eprintln!("⚠️ Failed to decode Battle.net product.db: {}", e),
+            },
+            Err(e) => eprintln!("⚠️ Failed to read Battle.net product.db: {}", e),
+        }
+    }
+
+    println!("-> Total {} Battle.net games cached.", games.len());
+    games
+}
+
+fn get_product_db_path(context: &ScanContext) -> PathBuf {
+    context.program_data().join("Battle.net").join("Agent").join("product.db")
+}
+
+/// Where [`PRODUCTS_CONFIG_FILE`] is looked for, independent of the
+/// directory we were started from.
+fn products_config_path() -> PathBuf {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(PRODUCTS_CONFIG_FILE)))
+        .unwrap_or_else(|| PathBuf::from(PRODUCTS_CONFIG_FILE))
+}
+
+/// Built-in product table, with entries from `config_path` layered on top.
+fn load_product_table(config_path: &Path) -> HashMap<String, ProductInfo> {
+    let mut products: HashMap<String, ProductInfo> = KNOWN_PRODUCTS
+        .iter()
+        .map(|(code, title, exe)| {
+            (code.to_string(), ProductInfo { title: title.to_string(), executable: exe.to_string() })
+        })
+        .collect();
+
+    if let Ok(content) = std::fs::read_to_string(config_path) {
+        match serde_json::from_str::<HashMap<String, ProductInfo>>(&content) {
+            Ok(overrides) => products.extend(overrides),
+            Err(e) => eprintln!("⚠️ Ignoring {}: {}", config_path.display(), e),
+        }
+    }
+
+    products
+}
+
+/// Decodes the Agent's `product.db`:
+///
+/// ```text
+/// Database        { repeated ProductInstall product_install = 1; ... }
+/// ProductInstall  { string uid = 1; string product_code = 2; UserSettings settings = 3;
+///                   CachedProductState cached_product_state = 4; ... }
+/// UserSettings    { string install_path = 1; ... }
+/// CachedProductState { BaseProductState base_product_state = 1; ... }
+/// BaseProductState   { bool installed = 1; bool playable = 2; ... }
+/// ```
+pub fn parse_product_db(data: &[u8]) -> Result<Vec<BattlenetInstall>, Box<dyn std::error::Error>> {
+    let database = Message::decode(data)?;
+
+    let installs = database
+        .messages(1)
+        .iter()
+        .filter_map(|install| {
+            let product_code = install.string(2)?;
+            let install_path = install.message(3)?.string(1).filter(|p| !p.is_empty())?;
+            let installed = install
+                .message(4)
+                .and_then(|state| state.message(1))
+                .and_then(|base| base.bool(1))
+                .unwrap_or(false);
+
+            Some(BattlenetInstall {
+                uid: install.string(1).unwrap_or_default(),
+                product_code,
+                install_path: PathBuf::from(install_path),
+                installed,
+            })
+        })
+        .collect();
+
+    Ok(installs)
+}
+
+fn resolve_games(installs: &[BattlenetInstall], products: &HashMap<String, ProductInfo>) -> Vec<BattlenetGame> {
+    installs
+        .iter()
+        .filter(|install| install.installed)
+        .filter_map(|install| {
+            let product = products.get(&install.product_code)?;
+            Some(BattlenetGame {
+                product_code: install.product_code.clone(),
+                title: product.title.clone(),
+                install_path: install.install_path.clone(),
+                executable: join_relative(&install.install_path, &product.executable),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Synthetic, not recorded from a client: hand-encoded in product.db's
+    /// protobuf layout with made-up install paths and only the fields we read.
+    const PRODUCT_DB: &[u8] = include_bytes!("../../tests/fixtures/battlenet/product.db");
+
+    #[test]
+    fn decodes_synthetic_product_db() {
+        let installs = parse_product_db(PRODUCT_DB).unwrap();
+        let codes: Vec<(&str, bool)> = installs.iter().map(|i| (i.product_code.as_str(), i.installed)).collect();
+        assert_eq!(codes, vec![("agent", true), ("wow", true), ("fenris", true), ("s2", false), ("bna", true)]);
+
+        let wow = &installs[1];
+        assert_eq!(wow.uid, "wow");
+        assert_eq!(wow.install_path, PathBuf::from("C:/Program Files (x86)/World of Warcraft"));
+    }
+
+    #[test]
+    fn maps_installed_products_to_executables() {
+        let installs = parse_product_db(PRODUCT_DB).unwrap();
+        let games = resolve_games(&installs, &load_product_table(Path::new("does-not-exist.json")));
+
+        let found: Vec<(&str, &str)> = games.iter().map(|g| (g.product_code.as_str(), g.title.as_str())).collect();
+        assert_eq!(found, vec![("wow", "World of Warcraft"), ("fenris", "Diablo IV")]);
+        assert_eq!(games[0].executable.file_name().unwrap(), "Wow.exe");
+        assert_eq!(games[1].executable.file_name().unwrap(), "Diablo IV.exe");
+    }
+
+    #[test]
+    fn config_overrides_product_table() {
+        let config = std::env::temp_dir().join(format!("battlenet_products_{}.json", std::process::id()));
+        std::fs::write(&config, r#"{ "fenris": { "title": "Diablo 4", "executable": "D4.exe" } }"#).unwrap();
+        let products = load_product_table(&config);
+        std::fs::remove_file(&config).unwrap();
+
+        assert_eq!(products["fenris"].executable, "D4.exe");
+        assert_eq!(products["wow"].title, "World of Warcraft");
+    }
+}
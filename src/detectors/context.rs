@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use crate::detectors::{join_relative, wine, Platform};
+use crate::registry::hive::HiveRegistry;
+use crate::registry::memory::MemoryRegistry;
+use crate::registry::{self, RegistrySource};
+
+/// The machine a scan looks at. A live scan reads the machine we run on; an
+/// offline scan reads a mounted Windows partition, a Wine prefix or a
+/// fixture directory as if it were that machine.
+///
+/// Paths found in launcher data, the registry or the environment are paths
+/// on the scanned machine and go through [`ScanContext::resolve`] before
+/// touching the filesystem.
+pub struct ScanContext {
+    /// Names the scanned machine in logs and the library cache.
+    label: String,
+    platform: Platform,
+    /// Host directory for each drive letter, or for `/` under the `'/'` key.
+    /// Empty for a live scan, where paths are used as they are.
+    drives: HashMap<char, PathBuf>,
+    env: HashMap<String, String>,
+    user_profiles: Vec<String>,
+    registry: Box<dyn RegistrySource>,
+    /// Files the registry was read from, empty for the live registry.
+    registry_files: Vec<PathBuf>,
+}
+
+impl ScanContext {
+    /// This machine, its environment, its registry and the current user.
+    pub fn live() -> Self {
+        let env: HashMap<String, String> = std::env::vars().collect();
+        let profile_var = if cfg!(target_os = "windows") { "USERPROFILE" } else { "HOME" };
+        let user_profiles = env.get(profile_var).cloned().into_iter().collect();
+
+        Self {
+            label: "live".to_string(),
+            platform: Platform::current(),
+            drives: HashMap::new(),
+            env,
+            user_profiles,
+            registry: registry::system_registry(),
+            registry_files: Vec::new(),
+        }
+    }
+
+    /// A machine of `platform` whose filesystem starts at `root`: its `C:`
+    /// drive for Windows, `/` otherwise. It starts with an empty environment
+    /// and registry.
+    pub fn offline(platform: Platform, root: impl Into<PathBuf>) -> Self {
+        let drive = if platform == Platform::Windows { 'c' } else { '/' };
+        let root = root.into();
+        Self {
+            label: root.display().to_string(),
+            platform,
+            drives: HashMap::from([(drive, root)]),
+            env: HashMap::new(),
+            user_profiles: Vec::new(),
+            registry: Box::new(MemoryRegistry::new()),
+            registry_files: Vec::new(),
+        }
+    }
+
+    /// A Windows install mounted at `windows_root`, scanned as `user` would
+    /// see it. Reads the registry from the install's hive files.
+    pub fn windows_install(windows_root: &Path, user: Option<&str>) -> Result<Self, Box<dyn std::error::Error>> {
+        let registry = HiveRegistry::from_windows_install(windows_root, user)?;
+        let mut context = Self::offline(Platform::Windows, windows_root).with_windows_folders();
+        context.registry_files = registry.files().to_vec();
+        context.registry = Box::new(registry);
+
+        Ok(match user {
+            Some(user) => context.with_windows_user(&format!(r"C:\Users\{}", user)),
+            None => context,
+        })
+    }
+
+    /// A Wine or Proton prefix: `drive_c` is `C:`, other drives follow the
+    /// `dosdevices` links, and the registry comes from `system.reg` and
+    /// `user.reg`.
+    pub fn wine_prefix(prefix: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let registry = registry::wine::load_prefix_registry(prefix)?;
+        let mut context = Self::offline(Platform::Windows, prefix.join("drive_c"))
+            .with_registry(Box::new(registry))
+            .with_windows_folders();
+        context.label = prefix.display().to_string();
+        context.registry_files = vec![prefix.join("system.reg"), prefix.join("user.reg")];
+
+        for (letter, target) in wine::get_drive_links(prefix) {
+            context = context.with_drive(letter, target);
+        }
+        if let Some(user) = wine::get_prefix_user(prefix) {
+            context = context.with_windows_user(&format!(r"C:\users\{}", user));
+        }
+
+        Ok(context)
+    }
+
+    /// The environment every Windows machine has, at default locations.
+    fn with_windows_folders(self) -> Self {
+        self.with_env("SystemDrive", "C:")
+            .with_env("SystemRoot", r"C:\Windows")
+            .with_env("ProgramData", r"C:\ProgramData")
+            .with_env("ProgramFiles", r"C:\Program Files")
+            .with_env("ProgramFiles(x86)", r"C:\Program Files (x86)")
+    }
+
+    fn with_windows_user(self, profile: &str) -> Self {
+        self.with_env("APPDATA", &format!(r"{}\AppData\Roaming", profile))
+            .with_env("LOCALAPPDATA", &format!(r"{}\AppData\Local", profile))
+            .with_env("USERPROFILE", profile)
+            .with_user_profile(profile)
+    }
+
+    pub fn with_env(mut self, name: &str, value: &str) -> Self {
+        self.env.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    pub fn with_user_profile(mut self, path: &str) -> Self {
+        self.user_profiles.push(path.to_string());
+        self
+    }
+
+    pub fn with_registry(mut self, registry: Box<dyn RegistrySource>) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    /// Maps another drive letter, e.g. a second partition holding a game
+    /// library, to a host directory.
+    pub fn with_drive(mut self, letter: char, root: impl Into<PathBuf>) -> Self {
+        self.drives.insert(letter.to_ascii_lowercase(), root.into());
+        self
+    }
+
+    /// True when scanning the machine we run on.
+    pub fn is_live(&self) -> bool {
+        self.drives.is_empty()
+    }
+
+    pub fn platform(&self) -> Platform {
+        self.platform
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn registry(&self) -> &dyn RegistrySource {
+        self.registry.as_ref()
+    }
+
+    /// Hive or `.reg` files behind [`ScanContext::registry`], for detectors
+    /// to list among their sources. Empty when reading the live registry.
+    pub fn registry_files(&self) -> &[PathBuf] {
+        &self.registry_files
+    }
+
+    /// Looks up an environment variable of the scanned machine, ignoring
+    /// case on Windows like Windows does.
+    pub fn env(&self, name: &str) -> Option<&str> {
+        let value = match self.platform {
+            Platform::Windows => self.env.iter().find(|(k, _)| k.eq_ignore_ascii_case(name)).map(|(_, v)| v),
+            _ => self.env.get(name),
+        };
+        value.map(|v| v.as_str()).filter(|v| !v.is_empty())
+    }
+
+    /// An environment variable holding a path, resolved to a host path.
+    pub fn env_path(&self, name: &str) -> Option<PathBuf> {
+        self.env(name).map(|value| self.resolve(value))
+    }
+
+    /// Like [`ScanContext::env_path`], falling back to the Windows default
+    /// location of a folder such as `ProgramFiles`.
+    pub fn env_path_or(&self, name: &str, default: &str) -> PathBuf {
+        self.env_path(name).unwrap_or_else(|| self.resolve(default))
+    }
+
+    pub fn program_data(&self) -> PathBuf {
+        self.env_path_or("ProgramData", r"C:\ProgramData")
+    }
+
+    /// Home folders of the users whose launchers we scan, as host paths.
+    pub fn user_profiles(&self) -> Vec<PathBuf> {
+        self.user_profiles.iter().map(|profile| self.resolve(profile)).collect()
+    }
+
+    /// Maps a path on the scanned machine to where it is on this one.
+    /// Paths on drives without a mapping are returned unchanged.
+    pub fn resolve(&self, path: impl AsRef<Path>) -> PathBuf {
+        let path = path.as_ref();
+        if self.drives.is_empty() {
+            return path.to_path_buf();
+        }
+
+        let text = path.to_string_lossy();
+        let mut chars = text.chars();
+        let (drive, rest) = match (chars.next(), chars.next()) {
+            (Some(letter), Some(':')) if letter.is_ascii_alphabetic() => (letter.to_ascii_lowercase(), &text[2..]),
+            (Some('/'), _) if self.platform != Platform::Windows => ('/', &text[1..]),
+            (Some('\\'), _) | (Some('/'), _) => ('c', &text[1..]),
+            _ => return path.to_path_buf(),
+        };
+
+        match self.drives.get(&drive) {
+            Some(root) => join_relative(root, rest),
+            None => path.to_path_buf(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_paths_under_the_scan_root() {
+        let context = ScanContext::offline(Platform::Windows, "/mnt/windows")
+            .with_drive('D', "/mnt/games")
+            .with_env("ProgramData", r"C:\ProgramData");
+
+        assert_eq!(
+            context.resolve(r"C:\Program Files (x86)\Steam"),
+            PathBuf::from("/mnt/windows/Program Files (x86)/Steam")
+        );
+        assert_eq!(context.resolve(r"d:\Games\Far Cry 6\"), PathBuf::from("/mnt/games/Games/Far Cry 6"));
+        assert_eq!(context.resolve(r"E:\Other"), PathBuf::from(r"E:\Other"));
+        assert_eq!(context.env_path("PROGRAMDATA"), Some(PathBuf::from("/mnt/windows/ProgramData")));
+
+        let linux = ScanContext::offline(Platform::Linux, "/tmp/fixture").with_user_profile("/home/deck");
+        assert_eq!(linux.user_profiles(), vec![PathBuf::from("/tmp/fixture/home/deck")]);
+        assert_eq!(linux.env("HOME"), None);
+    }
+}
@@ -0,0 +1,284 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use crate::detectors::context::ScanContext;
+use crate::detectors::{join_relative, GameDetector, Platform};
+use crate::models::{DetectedGame, DetectionConfidence};
+
+pub struct EaDetector;
+
+impl GameDetector for EaDetector {
+    fn id(&self) -> &'static str {
+        "ea"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "EA"
+    }
+
+    fn platforms(&self) -> &'static [Platform] {
+        &[Platform::Windows]
+    }
+
+    fn sources(&self, context: &ScanContext) -> Vec<PathBuf> {
+        // The settings files name custom library folders; each library
+        // folder holds one `installerdata.xml` per game.
+        let mut sources = vec![get_origin_settings_path(context)];
+        if let Some(ea_desktop) = get_ea_desktop_dir(context) {
+            sources.extend(ea_desktop_settings_files(&ea_desktop));
+            sources.push(ea_desktop);
+        }
+        for root in get_ea_install_roots(context) {
+            sources.extend(list_dir(&root).into_iter().map(|game| game.join("__Installer").join("installerdata.xml")));
+            sources.push(root);
+        }
+        sources
+    }
+
+    fn discover(&self, context: &ScanContext) -> Result<Vec<DetectedGame>, Box<dyn std::error::Error>> {
+        Ok(discover_ea_games(context))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EaGame {
+    pub content_ids: Vec<String>,
+    pub title: String,
+    pub install_path: PathBuf,
+    pub executable: Option<PathBuf>,
+    pub arguments: Option<String>,
+}
+
+pub fn discover_ea_games(context: &ScanContext) -> Vec<DetectedGame> {
+    let mut games = Vec::new();
+
+    for root in get_ea_install_roots(context) {
+        for game in find_ea_games(&root) {
+            let Some(executable) = game.executable else {
+                continue;
+            };
+            games.push(DetectedGame {
+                source: "ea".to_string(),
+                store_id: game.content_ids.into_iter().next(),
+                title: game.title,
+                install_dir: Some(game.install_path),
+                executables: vec![executable],
+                confidence: DetectionConfidence::High,
+            });
+        }
+    }
+
+    println!("-> Total {} EA games cached.", games.len());
+    games
+}
+
+/// Default EA app and Origin library folders plus any custom download
+/// location set in either client's settings.
+fn get_ea_install_roots(context: &ScanContext) -> Vec<PathBuf> {
+    let program_files = context.env_path_or("ProgramFiles", r"C:\Program Files");
+    let program_files_x86 = context.env_path_or("ProgramFiles(x86)", r"C:\Program Files (x86)");
+    let mut roots = vec![program_files.join("EA Games"), program_files_x86.join("Origin Games")];
+
+    if let Some(ea_desktop) = get_ea_desktop_dir(context) {
+        for settings in ea_desktop_settings_files(&ea_desktop) {
+            if let Ok(content) = std::fs::read_to_string(settings) {
+                roots.extend(content.lines().filter_map(|line| {
+                    let (key, value) = line.split_once('=')?;
+                    (key.trim() == "user.downloadinplacedir").then(|| context.resolve(value.trim()))
+                }));
+            }
+        }
+    }
+
+    if let Ok(content) = std::fs::read_to_string(get_origin_settings_path(context))
+        && let Ok(doc) = roxmltree::Document::parse(&content)
+    {
+        roots.extend(
+            doc.descendants()
+                .filter(|n| n.has_tag_name("Setting") && n.attribute("key") == Some("DownloadInPlaceDir"))
+                .filter_map(|n| n.attribute("value"))
+                .map(|dir| context.resolve(dir)),
+        );
+    }
+
+    let mut seen = HashSet::new();
+    roots.into_iter()
+        .filter(|root| root.is_dir())
+        .filter(|root| seen.insert(root.clone()))
+        .collect()
+}
+
+fn list_dir(dir: &Path) -> Vec<PathBuf> {
+    std::fs::read_dir(dir).map(|entries| entries.flatten().map(|e| e.path()).collect()).unwrap_or_default()
+}
+
+/// The `user_*.ini` settings files, leaving out the app's busy log and
+/// cache folders next to them.
+fn ea_desktop_settings_files(ea_desktop: &Path) -> Vec<PathBuf> {
+    list_dir(ea_desktop)
+        .into_iter()
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("user_") && name.ends_with(".ini"))
+        })
+        .collect()
+}
+
+/// Holds the EA app's `user_*.ini` settings files.
+fn get_ea_desktop_dir(context: &ScanContext) -> Option<PathBuf> {
+    Some(context.env_path("LOCALAPPDATA")?.join("Electronic Arts").join("EA Desktop"))
+}
+
+fn get_origin_settings_path(context: &ScanContext) -> PathBuf {
+    context.program_data().join("Origin").join("local.xml")
+}
+
+/// Reads `__Installer/installerdata.xml` from every game folder in `root`.
+pub fn find_ea_games(root: &Path) -> Vec<EaGame> {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return Vec::new();
+    };
+
+    entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .filter_map(|game_dir| {
+            let content = std::fs::read_to_string(game_dir.join("__Installer").join("installerdata.xml")).ok()?;
+            parse_installer_data(&content, &game_dir)
+        })
+        .collect()
+}
+
+/// Parses an EA/Origin DiP manifest. Both the 4.x layout (`gameTitles`) and
+/// the older one (`metadata/localeInfo/title`) are accepted.
+pub fn parse_installer_data(content: &str, install_path: &Path) -> Option<EaGame> {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let doc = roxmltree::Document::parse(content).ok()?;
+    let root = doc.root_element();
+
+    let content_ids: Vec<String> = root
+        .descendants()
+        .filter(|n| n.has_tag_name("contentID"))
+        .filter_map(|n| n.text())
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .collect();
+
+    let title = find_localized(&root, "gameTitle")
+        .or_else(|| find_localized(&root, "title"))
+        .unwrap_or_else(|| install_path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default());
+
+    let launcher = root
+        .descendants()
+        .filter(|n| n.has_tag_name("launcher") && n.parent().is_some_and(|p| p.has_tag_name("runtime")))
+        .filter(|n| child_text(n, "trial") != Some("1"))
+        .max_by_key(|n| child_text(n, "requires64BitOS") == Some("1"));
+
+    let executable = launcher
+        .and_then(|n| child_text(&n, "filePath"))
+        .map(|path| resolve_file_path(path, install_path));
+    let arguments = launcher
+        .and_then(|n| child_text(&n, "parameters"))
+        .filter(|p| !p.is_empty())
+        .map(|p| p.to_string());
+
+    Some(EaGame {
+        content_ids,
+        title,
+        install_path: install_path.to_path_buf(),
+        executable,
+        arguments,
+    })
+}
+
+/// Returns the `en_US` text of the first `tag` element, or any locale if
+/// there is no English one.
+fn find_localized(root: &roxmltree::Node, tag: &str) -> Option<String> {
+    let nodes: Vec<_> = root.descendants().filter(|n| n.has_tag_name(tag)).collect();
+    let node = nodes.iter()
+        .find(|n| {
+            let locale = n.attribute("locale").or_else(|| n.parent()?.attribute("locale"));
+            locale.is_some_and(|l| l.eq_ignore_ascii_case("en_US"))
+        })
+        .or(nodes.first())?;
+    Some(node.text()?.trim().to_string()).filter(|t| !t.is_empty())
+}
+
+fn child_text<'a>(node: &roxmltree::Node<'a, '_>, tag: &str) -> Option<&'a str> {
+    node.children()
+        .find(|c| c.has_tag_name(tag))?
+        .text()
+        .map(|t| t.trim())
+}
+
+/// Launcher paths start with a registry placeholder for the install folder,
+/// e.g. `[HKEY_LOCAL_MACHINE\SOFTWARE\EA Games\Battlefield 1\Install Dir]bf1.exe`.
+/// The manifest lives inside that folder, so the placeholder is replaced by it.
+fn resolve_file_path(file_path: &str, install_path: &Path) -> PathBuf {
+    let relative = match file_path.rfind(']') {
+        Some(end) if file_path.starts_with('[') => &file_path[end + 1..],
+        _ => file_path,
+    };
+    join_relative(install_path, relative)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INSTALLER_DATA: &str = include_str!("../../tests/fixtures/ea/installerdata.xml");
+
+    #[test]
+    fn parses_dip_manifest_v4() {
+        let install_path = Path::new("/games/Battlefield 1");
+        let game = parse_installer_data(INSTALLER_DATA, install_path).unwrap();
+
+        assert_eq!(game.content_ids, vec!["1026023", "1035052"]);
+        assert_eq!(game.title, "Battlefield™ 1");
+        assert_eq!(game.executable, Some(install_path.join("bf1.exe")));
+        assert_eq!(game.arguments, None);
+    }
+
+    #[test]
+    fn parses_legacy_manifest() {
+        let content = r#"<?xml version="1.0" encoding="utf-8"?>
+<DiPManifest version="2.2">
+  <contentIDs><contentID>70619</contentID></contentIDs>
+  <metadata>
+    <localeInfo locale="de_DE"><title>Die Sims 4</title></localeInfo>
+    <localeInfo locale="en_US"><title>The Sims 4</title></localeInfo>
+  </metadata>
+  <runtime>
+    <launcher>
+      <filePath>[HKEY_LOCAL_MACHINE\SOFTWARE\Maxis\The Sims 4\Install Dir]Game\Bin\TS4_x64.exe</filePath>
+      <parameters>-nolauncher</parameters>
+      <requires64BitOS>1</requires64BitOS>
+    </launcher>
+    <launcher>
+      <filePath>[HKEY_LOCAL_MACHINE\SOFTWARE\Maxis\The Sims 4\Install Dir]Game\Bin\TS4.exe</filePath>
+      <requires64BitOS>0</requires64BitOS>
+    </launcher>
+  </runtime>
+</DiPManifest>"#;
+        let install_path = Path::new("/games/The Sims 4");
+        let game = parse_installer_data(content, install_path).unwrap();
+
+        assert_eq!(game.title, "The Sims 4");
+        assert_eq!(game.executable, Some(install_path.join("Game").join("Bin").join("TS4_x64.exe")));
+        assert_eq!(game.arguments.as_deref(), Some("-nolauncher"));
+    }
+
+    #[test]
+    fn lists_only_ea_desktop_user_settings() {
+        let dir = std::env::temp_dir().join(format!("ea_desktop_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("Logs")).unwrap();
+        for name in ["user_1234567890.ini", "machine.ini", "cache.db"] {
+            std::fs::write(dir.join(name), "").unwrap();
+        }
+        let files = ea_desktop_settings_files(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(files, vec![dir.join("user_1234567890.ini")]);
+    }
+}
@@ -1,21 +1,50 @@
-use std::collections::HashSet;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use serde_json::Value;
+use crate::detectors::context::ScanContext;
+use crate::detectors::{join_relative, GameDetector, Platform};
+use crate::models::{DetectedGame, DetectionConfidence};
+use crate::registry::RootKey;
 
-pub fn discover_epic_games() -> HashSet<String> {
-    let mut games = HashSet::new();
-    #[cfg(target_os = "windows")]
-    let epic_path = get_epic_manifests_path()
-        .unwrap_or_else(|| r"C:\ProgramData\Epic\EpicGamesLauncher\Data\Manifests".to_string());
-    
-    if Path::new(&epic_path).exists() {
-        if let Ok(entries) = std::fs::read_dir(&epic_path) {
-            for entry in entries.flatten() {
-                if entry.path().extension().and_then(|s| s.to_str()) == Some("item") {
-                    if let Some(game_name) = parse_epic_manifest(&entry.path()) {
-                        games.insert(game_name);
-                    }
-                }
+pub struct EpicDetector;
+
+impl GameDetector for EpicDetector {
+    fn id(&self) -> &'static str {
+        "epic"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Epic"
+    }
+
+    fn platforms(&self) -> &'static [Platform] {
+        &[Platform::Windows]
+    }
+
+    fn sources(&self, context: &ScanContext) -> Vec<PathBuf> {
+        let manifests_path = get_epic_manifests_path(context);
+        let mut sources = context.registry_files().to_vec();
+        if let Ok(entries) = std::fs::read_dir(&manifests_path) {
+            sources.extend(entries.flatten().map(|entry| entry.path()));
+        }
+        sources.push(manifests_path);
+        sources
+    }
+
+    fn discover(&self, context: &ScanContext) -> Result<Vec<DetectedGame>, Box<dyn std::error::Error>> {
+        Ok(discover_epic_games(context))
+    }
+}
+
+pub fn discover_epic_games(context: &ScanContext) -> Vec<DetectedGame> {
+    let mut games = Vec::new();
+    let epic_path = get_epic_manifests_path(context);
+
+    if let Ok(entries) = std::fs::read_dir(&epic_path) {
+        for entry in entries.flatten() {
+            if entry.path().extension().and_then(|s| s.to_str()) == Some("item")
+                && let Some(game) = parse_epic_manifest(context, &entry.path())
+            {
+                games.push(game);
             }
         }
     }
@@ -23,30 +52,35 @@ pub fn discover_epic_games() -> HashSet<String> {
     games
 }
 
-fn parse_epic_manifest(path: &Path) -> Option<String> {
+fn parse_epic_manifest(context: &ScanContext, path: &Path) -> Option<DetectedGame> {
     let content = std::fs::read_to_string(path).ok()?;
     let json: Value = serde_json::from_str(&content).ok()?;
 
+    let install_location = context.resolve(json.get("InstallLocation")?.as_str()?);
     let exe_path = json.get("LaunchExecutable")?.as_str()?;
-    let exe_name = Path::new(exe_path)
-        .file_name()?
-        .to_string_lossy()
-        .to_string();
+    let app_name = json.get("AppName").and_then(|n| n.as_str());
+    let title = json
+        .get("DisplayName")
+        .and_then(|n| n.as_str())
+        .or(app_name)
+        .unwrap_or(exe_path);
 
-    Some(exe_name)
+    Some(DetectedGame {
+        source: "epic".to_string(),
+        store_id: app_name.map(|n| n.to_string()),
+        title: title.to_string(),
+        executables: vec![join_relative(&install_location, exe_path)],
+        install_dir: Some(install_location),
+        confidence: DetectionConfidence::High,
+    })
 }
 
-fn get_epic_manifests_path() -> Option<String> {
-    use winreg::RegKey;
-    use winreg::enums::*;
-
-    let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
-    if let Ok(epic_key) = hklm.open_subkey(r"SOFTWARE\WOW6432Node\Epic Games\EpicGamesLauncher") {
-        if let Ok(install_location) = epic_key.get_value::<String, _>("AppDataPath") {
-            println!("Location found: {}", install_location);
-            return Some(format!(r"{}\Manifests", install_location));
-        }
+fn get_epic_manifests_path(context: &ScanContext) -> PathBuf {
+    let epic_key = r"SOFTWARE\WOW6432Node\Epic Games\EpicGamesLauncher";
+    if let Some(app_data_path) = context.registry().string(RootKey::LocalMachine, epic_key, "AppDataPath") {
+        println!("Location found: {}", app_data_path);
+        return context.resolve(app_data_path).join("Manifests");
     }
 
-    Some(r"C:\ProgramData\Epic\EpicGamesLauncher\Data\Manifests".to_string())
-}
\ No newline at end of file
+    join_relative(&context.program_data(), r"Epic\EpicGamesLauncher\Data\Manifests")
+}
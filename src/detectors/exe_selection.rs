@@ -0,0 +1,376 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+use crate::parsers::pe::{self, Machine, PeInfo, Subsystem};
+
+/// How many folders below the install directory candidates are looked for.
+/// Unreal games keep the real binary in `<Project>/Binaries/Win64`.
+const MAX_DEPTH: usize = 5;
+
+/// Candidates scoring below this are never picked as a game's executable.
+const MIN_SCORE: i32 = 0;
+
+/// Substrings of file names that are never the game itself: uninstallers,
+/// installers, crash handlers, redistributables, anti-cheat services and
+/// dedicated servers.
+pub const EXCLUDED_NAMES: &[&str] = &[
+    "unins", "setup", "install", "crash", "report", "redist", "prerequisite", "prereq",
+    "dxsetup", "dotnet", "vc_redist", "dedicated", "server", "_ds", "helper",
+    "easyanticheat", "beservice", "cefprocess", "webhelper", "touchup", "cleanup",
+];
+
+/// Names that can be the game but usually are a front-end for it.
+const PENALIZED_NAMES: &[(&str, i32)] = &[
+    ("launcher", -30),
+    ("config", -20),
+    ("settings", -20),
+    ("update", -20),
+    ("patcher", -20),
+    ("editor", -15),
+    ("benchmark", -15),
+    ("tool", -15),
+];
+
+/// Folders that only hold redistributables and installer data.
+const SKIPPED_DIRS: &[&str] = &["redist", "__installer", "directx", "prereq", "easyanticheat", "battleye"];
+
+/// Files that ship next to game binaries made with common engines and SDKs.
+pub const GAME_ENGINE_FILES: &[&str] = &[
+    "unityplayer.dll", "ue4prerequisites", "unrealengine", "cryengine",
+    "fmod.dll", "bink2w64.dll", "steam_api.dll", "steam_api64.dll",
+    "eossdk-win64-shipping.dll", "galaxydll.dll", "galaxy64.dll",
+];
+
+/// Unreal Engine packaged build suffixes, e.g. `Hades-Win64-Shipping.exe`.
+const SHIPPING_SUFFIXES: &[&str] = &["-win64-shipping", "-wingdk-shipping", "-shipping"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoreReason {
+    pub points: i32,
+    pub description: String,
+}
+
+impl fmt::Display for ScoreReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:+} {}", self.points, self.description)
+    }
+}
+
+/// A candidate binary and how its score was reached.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoredExecutable {
+    pub path: PathBuf,
+    pub size: u64,
+    pub score: i32,
+    pub reasons: Vec<ScoreReason>,
+}
+
+impl ScoredExecutable {
+    fn add(&mut self, points: i32, description: impl Into<String>) {
+        self.score += points;
+        self.reasons.push(ScoreReason { points, description: description.into() });
+    }
+}
+
+/// Ranks every game binary under `install_dir`, best candidate first.
+/// Excluded names and redistributable folders are left out entirely.
+pub fn rank_executables(install_dir: &Path, title: Option<&str>) -> Vec<ScoredExecutable> {
+    let mut ranked: Vec<ScoredExecutable> = WalkDir::new(install_dir)
+        .max_depth(MAX_DEPTH)
+        .into_iter()
+        .filter_entry(|entry| entry.depth() == 0 || !entry.file_type().is_dir() || !is_skipped_dir(entry.path()))
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file() && is_game_binary(entry.path()))
+        .filter_map(|entry| score_executable(entry.path(), entry.depth() - 1, title))
+        .collect();
+
+    ranked.sort_by(|a, b| {
+        b.score.cmp(&a.score)
+            .then(b.size.cmp(&a.size))
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    ranked
+}
+
+/// The top ranked executable, if any scores well enough to be trusted.
+pub fn best_executable(install_dir: &Path, title: Option<&str>) -> Option<PathBuf> {
+    rank_executables(install_dir, title)
+        .into_iter()
+        .next()
+        .filter(|candidate| candidate.score >= MIN_SCORE)
+        .map(|candidate| candidate.path)
+}
+
+/// Scores one binary found `depth` folders below the install directory.
+/// Returns `None` when its name is on the exclusion list.
+pub fn score_executable(path: &Path, depth: usize, title: Option<&str>) -> Option<ScoredExecutable> {
+    let file_name = path.file_name()?.to_string_lossy().to_lowercase();
+    if is_excluded(&file_name) {
+        return None;
+    }
+
+    let size = std::fs::metadata(path).ok()?.len();
+    let mut candidate = ScoredExecutable { path: path.to_path_buf(), size, score: 0, reasons: Vec::new() };
+
+    let size_mb = size as f64 / (1024.0 * 1024.0);
+    match size_mb {
+        s if s >= 200.0 => candidate.add(25, format!("very large binary ({:.1} MB)", s)),
+        s if s >= 50.0 => candidate.add(20, format!("large binary ({:.1} MB)", s)),
+        s if s >= 10.0 => candidate.add(10, format!("medium binary ({:.1} MB)", s)),
+        s if s < 1.0 => candidate.add(-10, format!("small binary ({:.0} KB)", s * 1024.0)),
+        _ => {}
+    }
+
+    if depth > 0 {
+        candidate.add(-5 * depth as i32, format!("{} folder(s) below the install directory", depth));
+    }
+
+    let stem = file_name.strip_suffix(".exe").unwrap_or(&file_name);
+    if SHIPPING_SUFFIXES.iter().any(|suffix| stem.ends_with(suffix)) {
+        candidate.add(30, "Unreal Engine shipping build");
+    }
+
+    for (pattern, points) in PENALIZED_NAMES {
+        if stem.contains(pattern) {
+            candidate.add(*points, format!("name contains \"{}\"", pattern));
+        }
+    }
+
+    if let Some(title) = title {
+        score_title_similarity(&mut candidate, stem, title);
+    }
+
+    if file_name.ends_with(".exe")
+        && let Ok(info) = pe::read_file(path)
+    {
+        score_pe_info(&mut candidate, &info, title);
+    }
+
+    if let Some(dir) = path.parent() {
+        score_siblings(&mut candidate, dir, stem);
+    }
+
+    Some(candidate)
+}
+
+pub fn is_excluded(file_name: &str) -> bool {
+    let name = file_name.to_lowercase();
+    EXCLUDED_NAMES.iter().any(|pattern| name.contains(pattern))
+}
+
+fn is_skipped_dir(path: &Path) -> bool {
+    let name = path.file_name().map(|n| n.to_string_lossy().to_lowercase()).unwrap_or_default();
+    SKIPPED_DIRS.iter().any(|pattern| name.contains(pattern))
+}
+
+fn score_title_similarity(candidate: &mut ScoredExecutable, stem: &str, title: &str) {
+    let base = SHIPPING_SUFFIXES
+        .iter()
+        .find_map(|suffix| stem.strip_suffix(suffix))
+        .unwrap_or(stem);
+    let name = normalize(base);
+    let title_norm = normalize(title);
+    if name.is_empty() || title_norm.is_empty() {
+        return;
+    }
+
+    let initials: String = title
+        .split(|c: char| !c.is_alphanumeric())
+        .filter_map(|word| word.chars().next())
+        .flat_map(|c| c.to_lowercase())
+        .collect();
+
+    if name == title_norm {
+        candidate.add(40, "name matches the title");
+    } else if name.len() >= 3 && (title_norm.contains(&name) || name.contains(&title_norm)) {
+        candidate.add(25, "name resembles the title");
+    } else if initials.len() >= 2 && name.starts_with(&initials) {
+        candidate.add(20, "name matches the title's initials");
+    }
+}
+
+/// Console tools and 32-bit helpers ship next to many games; the version
+/// resource often names the product outright.
+fn score_pe_info(candidate: &mut ScoredExecutable, info: &PeInfo, title: Option<&str>) {
+    if info.subsystem == Subsystem::WindowsConsole {
+        candidate.add(-40, "console program");
+    }
+    if info.machine == Machine::I386 {
+        candidate.add(-10, "32-bit binary");
+    }
+
+    let product_name = info.version.as_ref().and_then(|v| v.product_name.as_deref());
+    if let (Some(product_name), Some(title)) = (product_name, title) {
+        let product = normalize(product_name);
+        let title = normalize(title);
+        if !product.is_empty() && (product.contains(&title) || title.contains(&product)) {
+            candidate.add(20, format!("product name \"{}\" matches the title", product_name));
+        }
+    }
+}
+
+fn score_siblings(candidate: &mut ScoredExecutable, dir: &Path, stem: &str) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let siblings: Vec<String> = entries.flatten().map(|e| e.file_name().to_string_lossy().to_lowercase()).collect();
+
+    // Unity games keep their assets in `<exe name>_Data` next to the binary.
+    let data_dir = format!("{}_data", stem);
+    if siblings.contains(&data_dir) {
+        candidate.add(30, "Unity data folder next to it");
+    }
+
+    let engine_file = siblings
+        .iter()
+        .find(|name| GAME_ENGINE_FILES.iter().any(|engine_file| name.contains(engine_file)));
+    if let Some(engine_file) = engine_file {
+        candidate.add(15, format!("next to {}", engine_file));
+    }
+}
+
+fn normalize(name: &str) -> String {
+    name.chars().filter(|c| c.is_alphanumeric()).flat_map(|c| c.to_lowercase()).collect()
+}
+
+/// Accepts Windows executables and native ELF executables. Shared objects
+/// share the ELF header, so they are filtered out by name.
+pub fn is_game_binary(path: &Path) -> bool {
+    let Some(name) = path.file_name().map(|n| n.to_string_lossy().to_lowercase()) else {
+        return false;
+    };
+
+    if name.ends_with(".exe") {
+        return true;
+    }
+
+    if name.ends_with(".so") || name.contains(".so.") || !path.is_file() {
+        return false;
+    }
+
+    is_elf_executable(path)
+}
+
+fn is_elf_executable(path: &Path) -> bool {
+    use std::io::Read;
+
+    let mut header = [0u8; 18];
+    let Ok(mut file) = std::fs::File::open(path) else {
+        return false;
+    };
+    if file.read_exact(&mut header).is_err() || &header[..4] != b"\x7fELF" {
+        return false;
+    }
+
+    // e_type: ET_EXEC (2) or ET_DYN (3, position-independent executables).
+    let e_type = match header[5] {
+        2 => u16::from_be_bytes([header[16], header[17]]),
+        _ => u16::from_le_bytes([header[16], header[17]]),
+    };
+    if !matches!(e_type, 2 | 3) {
+        return false;
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if let Ok(metadata) = file.metadata() {
+            return metadata.permissions().mode() & 0o111 != 0;
+        }
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_file(path: &Path, size: u64) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::File::create(path).unwrap().set_len(size).unwrap();
+    }
+
+    fn temp_game_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("exe_selection_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn prefers_unreal_shipping_build_over_bootstrap() {
+        let dir = temp_game_dir("unreal");
+        make_file(&dir.join("Hades.exe"), 300 << 10);
+        make_file(&dir.join("Hades").join("Binaries").join("Win64").join("Hades-Win64-Shipping.exe"), 80 << 20);
+        make_file(&dir.join("Engine").join("Binaries").join("Win64").join("CrashReportClient.exe"), 20 << 20);
+        make_file(&dir.join("_CommonRedist").join("vcredist_x64.exe"), 15 << 20);
+        make_file(&dir.join("unins000.exe"), 3 << 20);
+
+        let ranked = rank_executables(&dir, Some("Hades"));
+        let best = best_executable(&dir, Some("Hades"));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let names: Vec<String> = ranked
+            .iter()
+            .map(|c| c.path.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, vec!["Hades-Win64-Shipping.exe", "Hades.exe"]);
+        assert_eq!(ranked[0].score, 20 - 15 + 30 + 40);
+        assert!(ranked[0].reasons.iter().any(|r| r.description == "Unreal Engine shipping build"));
+        assert_eq!(best.unwrap().file_name().unwrap(), "Hades-Win64-Shipping.exe");
+    }
+
+    #[test]
+    fn scores_unity_stub_next_to_its_data_folder() {
+        let dir = temp_game_dir("unity");
+        make_file(&dir.join("Hollow Knight.exe"), 650 << 10);
+        make_file(&dir.join("UnityPlayer.dll"), 25 << 20);
+        make_file(&dir.join("UnityCrashHandler64.exe"), 1 << 20);
+        make_file(&dir.join("Hollow Knight_Data").join("globalgamemanagers"), 1 << 10);
+        make_file(&dir.join("GameLauncher.exe"), 12 << 20);
+
+        let ranked = rank_executables(&dir, Some("Hollow Knight"));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(ranked.len(), 2);
+        assert_eq!(ranked[0].path.file_name().unwrap(), "Hollow Knight.exe");
+        assert_eq!(ranked[0].score, -10 + 40 + 30 + 15);
+        assert_eq!(ranked[1].path.file_name().unwrap(), "GameLauncher.exe");
+        assert_eq!(ranked[1].score, 10 - 30 + 15);
+    }
+
+    #[test]
+    fn rejects_when_nothing_scores_well() {
+        let dir = temp_game_dir("tiny");
+        make_file(&dir.join("tool.exe"), 200 << 10);
+
+        assert_eq!(best_executable(&dir, None), None);
+        assert_eq!(rank_executables(&dir, None).len(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn reads_signals_from_pe_headers() {
+        let dir = temp_game_dir("pe");
+        let fixtures = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join("pe");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::copy(fixtures.join("game.exe"), dir.join("hk.exe")).unwrap();
+        std::fs::copy(fixtures.join("tool32.exe"), dir.join("hkmod.exe")).unwrap();
+
+        let ranked = rank_executables(&dir, Some("Hollow Knight"));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(ranked[0].path.file_name().unwrap(), "hk.exe");
+        assert_eq!(ranked[0].score, -10 + 20 + 20);
+        assert_eq!(ranked[1].score, -10 + 20 - 40 - 10);
+        assert!(ranked[1].reasons.iter().any(|r| r.description == "console program"));
+    }
+
+    #[test]
+    fn matches_title_initials() {
+        let mut candidate = ScoredExecutable { path: PathBuf::from("GTA5.exe"), size: 0, score: 0, reasons: Vec::new() };
+        score_title_similarity(&mut candidate, "gtav", "Grand Theft Auto V");
+        assert_eq!(candidate.score, 20);
+        assert_eq!(candidate.reasons[0].to_string(), "+20 name matches the title's initials");
+    }
+}
@@ -0,0 +1,261 @@
+use rusqlite::{Connection, OpenFlags};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use crate::detectors::context::ScanContext;
+use crate::detectors::{join_relative, GameDetector, Platform};
+use crate::models::{DetectedGame, DetectionConfidence};
+use crate::registry::{self, RootKey};
+
+pub struct GogDetector;
+
+impl GameDetector for GogDetector {
+    fn id(&self) -> &'static str {
+        "gog"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "GOG"
+    }
+
+    fn platforms(&self) -> &'static [Platform] {
+        &[Platform::Windows, Platform::Linux]
+    }
+
+    fn sources(&self, context: &ScanContext) -> Vec<PathBuf> {
+        let mut sources = context.registry_files().to_vec();
+        sources.push(get_galaxy_db_path(context));
+        sources.extend(get_standalone_game_dirs(context));
+        sources
+    }
+
+    fn discover(&self, context: &ScanContext) -> Result<Vec<DetectedGame>, Box<dyn std::error::Error>> {
+        Ok(discover_gog_games(context))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GogGame {
+    pub product_id: String,
+    pub title: String,
+    pub install_path: PathBuf,
+    pub executable: Option<PathBuf>,
+    pub arguments: Option<String>,
+}
+
+pub fn discover_gog_games(context: &ScanContext) -> Vec<DetectedGame> {
+    let mut found: Vec<GogGame> = Vec::new();
+
+    let db_path = get_galaxy_db_path(context);
+    if db_path.exists() {
+        match parse_galaxy_database(&db_path) {
+            Ok(games) => found.extend(games.into_iter().map(|mut game| {
+                game.install_path = context.resolve(&game.install_path);
+                game.executable = game.executable.map(|exe| context.resolve(exe));
+                game
+            })),
+            Err(e) => eprintln!("⚠️ Failed to read GOG Galaxy database: {}", e),
+        }
+    }
+
+    // Standalone (offline installer) games are not in the Galaxy database.
+    for dir in get_standalone_game_dirs(context) {
+        for game in find_standalone_games(&dir) {
+            if !found.iter().any(|g| g.product_id == game.product_id) {
+                found.push(game);
+            }
+        }
+    }
+
+    let games: Vec<DetectedGame> = found
+        .into_iter()
+        .filter_map(|g| {
+            Some(DetectedGame {
+                source: "gog".to_string(),
+                executables: vec![g.executable?],
+                store_id: Some(g.product_id),
+                title: g.title,
+                install_dir: Some(g.install_path),
+                confidence: DetectionConfidence::High,
+            })
+        })
+        .collect();
+
+    println!("-> Total {} GOG games cached.", games.len());
+    games
+}
+
+fn get_galaxy_db_path(context: &ScanContext) -> PathBuf {
+    context.program_data()
+        .join("GOG.com")
+        .join("Galaxy")
+        .join("storage")
+        .join("galaxy-2.0.db")
+}
+
+/// Reads installed products with their first stored title and the first
+/// primary play task's launch parameters, as Galaxy keeps one title per
+/// language and one play task per user.
+pub fn parse_galaxy_database(db_path: &Path) -> Result<Vec<GogGame>, Box<dyn std::error::Error>> {
+    let conn = Connection::open_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let mut stmt = conn.prepare(
+        "SELECT ibp.productId, ibp.installationPath,
+                (SELECT ld.title FROM LimitedDetails ld
+                 WHERE ld.productId = ibp.productId
+                 ORDER BY ld.id LIMIT 1),
+                ptlp.executablePath, ptlp.commandLineArgs
+         FROM InstalledBaseProducts ibp
+         LEFT JOIN PlayTaskLaunchParameters ptlp ON ptlp.playTaskId = (
+             SELECT pt.id FROM PlayTasks pt
+             WHERE pt.gameReleaseKey = 'gog_' || ibp.productId AND pt.isPrimary = 1
+             ORDER BY pt.\"order\", pt.id LIMIT 1
+         )",
+    )?;
+
+    let games = stmt
+        .query_map([], |row| {
+            let product_id: i64 = row.get(0)?;
+            let install_path: String = row.get(1)?;
+            let title: Option<String> = row.get(2)?;
+            let executable: Option<String> = row.get(3)?;
+            let arguments: Option<String> = row.get(4)?;
+
+            Ok(GogGame {
+                product_id: product_id.to_string(),
+                title: title.unwrap_or_else(|| product_id.to_string()),
+                install_path: PathBuf::from(install_path),
+                executable: executable.filter(|e| !e.is_empty()).map(PathBuf::from),
+                arguments: arguments.filter(|a| !a.is_empty()),
+            })
+        })?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    Ok(games)
+}
+
+fn get_standalone_game_dirs(context: &ScanContext) -> Vec<PathBuf> {
+    let games_key = r"SOFTWARE\WOW6432Node\GOG.com\Games";
+    let registry = context.registry();
+    let mut dirs = default_standalone_game_dirs(context);
+
+    for game_id in registry.subkeys(RootKey::LocalMachine, games_key) {
+        if let Some(path) = registry.string(RootKey::LocalMachine, &registry::join_key(games_key, &game_id), "path") {
+            dirs.push(context.resolve(path));
+        }
+    }
+
+    dirs
+}
+
+fn default_standalone_game_dirs(context: &ScanContext) -> Vec<PathBuf> {
+    if context.platform() == Platform::Windows {
+        return vec![context.resolve(r"C:\GOG Games")];
+    }
+    context.user_profiles().into_iter().map(|home| home.join("GOG Games")).collect()
+}
+
+/// Looks for `goggame-<id>.info` files in `dir` and in its immediate
+/// subfolders, so both a game folder and a library folder can be passed.
+pub fn find_standalone_games(dir: &Path) -> Vec<GogGame> {
+    let mut games = Vec::new();
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return games;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            if let Ok(sub_entries) = std::fs::read_dir(&path) {
+                games.extend(sub_entries.flatten().filter_map(|e| read_info_file(&e.path(), &path)));
+            }
+        } else if let Some(game) = read_info_file(&path, dir) {
+            games.push(game);
+        }
+    }
+
+    games
+}
+
+fn read_info_file(path: &Path, install_path: &Path) -> Option<GogGame> {
+    let file_name = path.file_name()?.to_str()?;
+    let product_id = file_name.strip_prefix("goggame-")?.strip_suffix(".info")?;
+    read_game_info(install_path, product_id)
+}
+
+/// Parses the `goggame-<id>.info` GOG ships in every install folder. The
+/// primary `playTasks` entry names the real executable and its arguments.
+pub fn read_game_info(install_path: &Path, product_id: &str) -> Option<GogGame> {
+    let content = std::fs::read_to_string(install_path.join(format!("goggame-{}.info", product_id))).ok()?;
+    let info: Value = serde_json::from_str(&content).ok()?;
+
+    let task = primary_play_task(&info);
+    let executable = task
+        .and_then(|t| t.get("path")?.as_str())
+        .map(|exe| join_relative(install_path, exe));
+    let arguments = task
+        .and_then(|t| t.get("arguments")?.as_str())
+        .filter(|a| !a.is_empty())
+        .map(|a| a.to_string());
+
+    Some(GogGame {
+        product_id: product_id.to_string(),
+        title: info.get("name").and_then(|n| n.as_str()).unwrap_or(product_id).to_string(),
+        install_path: install_path.to_path_buf(),
+        executable,
+        arguments,
+    })
+}
+
+fn primary_play_task(info: &Value) -> Option<&Value> {
+    let tasks = info.get("playTasks")?.as_array()?;
+    let is_file_task = |t: &&Value| t.get("type").and_then(|c| c.as_str()) == Some("FileTask");
+
+    tasks.iter()
+        .filter(is_file_task)
+        .find(|t| t.get("isPrimary").and_then(|p| p.as_bool()) == Some(true))
+        .or_else(|| tasks.iter().find(is_file_task))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture_dir() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join("gog")
+    }
+
+    #[test]
+    fn reads_titles_and_primary_play_tasks_from_galaxy_db() {
+        let mut games = parse_galaxy_database(&fixture_dir().join("galaxy-2.0.db")).unwrap();
+        games.sort_by(|a, b| a.product_id.cmp(&b.product_id));
+        assert_eq!(games.len(), 2);
+
+        let witcher = &games[0];
+        assert_eq!(witcher.product_id, "1207664663");
+        assert_eq!(witcher.title, "The Witcher 3: Wild Hunt");
+        assert_eq!(witcher.install_path, PathBuf::from(r"C:\GOG Games\The Witcher 3 Wild Hunt GOTY"));
+        assert_eq!(
+            witcher.executable,
+            Some(PathBuf::from(r"C:\GOG Games\The Witcher 3 Wild Hunt GOTY\bin\x64\witcher3.exe"))
+        );
+        assert_eq!(witcher.arguments, None);
+
+        let cyberpunk = &games[1];
+        assert_eq!(cyberpunk.title, "Cyberpunk 2077");
+        assert_eq!(cyberpunk.arguments.as_deref(), Some("--launcher-skip"));
+    }
+
+    #[test]
+    fn reads_standalone_info_files() {
+        let games = find_standalone_games(&fixture_dir().join("games"));
+        assert_eq!(games.len(), 1);
+
+        let game = &games[0];
+        let install_path = fixture_dir().join("games").join("Disco Elysium");
+        assert_eq!(game.product_id, "1771589310");
+        assert_eq!(game.title, "Disco Elysium");
+        assert_eq!(game.executable, Some(install_path.join("disco.exe")));
+        assert_eq!(game.arguments.as_deref(), Some("-windowed"));
+        assert_eq!(game.install_path, install_path);
+    }
+}
@@ -0,0 +1,352 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use serde_json::Value;
+use crate::detectors::context::ScanContext;
+use crate::detectors::{gog, join_relative, GameDetector, Platform};
+use crate::models::{DetectedGame, DetectionConfidence};
+
+pub struct HeroicDetector;
+
+impl GameDetector for HeroicDetector {
+    fn id(&self) -> &'static str {
+        "heroic"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Heroic"
+    }
+
+    fn platforms(&self) -> &'static [Platform] {
+        &[Platform::Windows, Platform::Linux, Platform::MacOs]
+    }
+
+    fn sources(&self, context: &ScanContext) -> Vec<PathBuf> {
+        get_heroic_config_dirs(context)
+            .into_iter()
+            .flat_map(|config_dir| {
+                let nile_dir = config_dir.join("nile_config").join("nile");
+                [
+                    config_dir.join("legendaryConfig").join("legendary").join("installed.json"),
+                    config_dir.join("gog_store").join("installed.json"),
+                    nile_dir.join("installed.json"),
+                    nile_dir.join("library.json"),
+                ]
+            })
+            .collect()
+    }
+
+    fn discover(&self, context: &ScanContext) -> Result<Vec<DetectedGame>, Box<dyn std::error::Error>> {
+        Ok(discover_heroic_games(context))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HeroicStore {
+    Epic,
+    Gog,
+    Amazon,
+}
+
+impl HeroicStore {
+    /// The `source` of games installed through this backend. Each store gets
+    /// its own, as their ids would collide otherwise.
+    pub fn source(self) -> &'static str {
+        match self {
+            HeroicStore::Epic => "heroic-epic",
+            HeroicStore::Gog => "heroic-gog",
+            HeroicStore::Amazon => "heroic-amazon",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeroicGame {
+    /// The store backend's id for the game: Epic app name, GOG product id
+    /// or Amazon product id.
+    pub app_name: String,
+    pub title: String,
+    pub install_path: PathBuf,
+    pub executable: Option<PathBuf>,
+    pub store: HeroicStore,
+}
+
+pub fn discover_heroic_games(context: &ScanContext) -> Vec<DetectedGame> {
+    let mut games = Vec::new();
+
+    for config_dir in get_heroic_config_dirs(context) {
+        for game in find_heroic_games(context, &config_dir) {
+            let Some(executable) = game.executable else {
+                continue;
+            };
+            games.push(DetectedGame {
+                source: game.store.source().to_string(),
+                store_id: Some(game.app_name),
+                title: game.title,
+                install_dir: Some(game.install_path),
+                executables: vec![executable],
+                confidence: DetectionConfidence::High,
+            });
+        }
+    }
+
+    println!("-> Total {} Heroic games cached.", games.len());
+    games
+}
+
+fn get_heroic_config_dirs(context: &ScanContext) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+
+    if let Some(config) = context.env_path("XDG_CONFIG_HOME") {
+        dirs.push(config.join("heroic"));
+    }
+    for home in context.user_profiles() {
+        dirs.push(home.join(".config").join("heroic"));
+        dirs.push(home.join(".var").join("app").join("com.heroicgameslauncher.hgl").join("config").join("heroic"));
+    }
+    if let Some(appdata) = context.env_path("APPDATA") {
+        dirs.push(appdata.join("heroic"));
+    }
+
+    let mut seen = HashSet::new();
+    dirs.into_iter()
+        .filter(|dir| dir.is_dir())
+        .filter(|dir| seen.insert(std::fs::canonicalize(dir).unwrap_or_else(|_| dir.clone())))
+        .collect()
+}
+
+/// Prefixes set as `winePrefix` in Heroic's per-game settings,
+/// `GamesConfig/<app name>.json`.
+pub fn get_wine_prefixes(context: &ScanContext) -> Vec<PathBuf> {
+    let mut prefixes = Vec::new();
+
+    for config_dir in get_heroic_config_dirs(context) {
+        let Ok(entries) = std::fs::read_dir(config_dir.join("GamesConfig")) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Ok(content) = std::fs::read_to_string(entry.path()) else {
+                continue;
+            };
+            let Ok(Value::Object(settings)) = serde_json::from_str::<Value>(&content) else {
+                continue;
+            };
+            prefixes.extend(
+                settings
+                    .values()
+                    .filter_map(|game| game.get("winePrefix")?.as_str())
+                    .filter(|prefix| !prefix.is_empty())
+                    .map(|prefix| context.resolve(prefix)),
+            );
+        }
+    }
+
+    prefixes
+}
+
+/// Reads every store backend Heroic manages under one config directory.
+/// Install paths and executables are resolved to host paths.
+pub fn find_heroic_games(context: &ScanContext, config_dir: &Path) -> Vec<HeroicGame> {
+    let mut games = Vec::new();
+
+    let legendary = config_dir.join("legendaryConfig").join("legendary").join("installed.json");
+    if let Ok(content) = std::fs::read_to_string(legendary) {
+        games.extend(parse_legendary_installed(context, &content));
+    }
+
+    let gog = config_dir.join("gog_store").join("installed.json");
+    if let Ok(content) = std::fs::read_to_string(gog) {
+        games.extend(parse_gog_installed(context, &content));
+    }
+
+    let nile_dir = config_dir.join("nile_config").join("nile");
+    if let Ok(content) = std::fs::read_to_string(nile_dir.join("installed.json")) {
+        let library = std::fs::read_to_string(nile_dir.join("library.json")).ok();
+        games.extend(parse_nile_installed(context, &content, library.as_deref()));
+    }
+
+    games
+}
+
+/// legendary's `installed.json` is an object keyed by app name, with the
+/// launch executable stored relative to the install path.
+fn parse_legendary_installed(context: &ScanContext, content: &str) -> Vec<HeroicGame> {
+    let Ok(Value::Object(installed)) = serde_json::from_str::<Value>(content) else {
+        return Vec::new();
+    };
+
+    installed
+        .iter()
+        .filter_map(|(app_name, game)| {
+            let install_path = context.resolve(game.get("install_path")?.as_str()?);
+            let title = game.get("title").and_then(|v| v.as_str())
+                .unwrap_or(app_name)
+                .to_string();
+            let executable = game.get("executable")
+                .and_then(|v| v.as_str())
+                .filter(|exe| !exe.is_empty())
+                .map(|exe| join_relative(&install_path, exe));
+
+            Some(HeroicGame { app_name: app_name.clone(), title, install_path, executable, store: HeroicStore::Epic })
+        })
+        .collect()
+}
+
+/// gogdl only records where each game went; title and executable come from
+/// the `goggame-<id>.info` file GOG ships in the install folder.
+fn parse_gog_installed(context: &ScanContext, content: &str) -> Vec<HeroicGame> {
+    let Ok(json) = serde_json::from_str::<Value>(content) else {
+        return Vec::new();
+    };
+    let Some(installed) = json.get("installed").and_then(|v| v.as_array()) else {
+        return Vec::new();
+    };
+
+    installed
+        .iter()
+        .filter_map(|game| {
+            let app_name = game.get("appName")?.as_str()?;
+            let install_path = context.resolve(game.get("install_path")?.as_str()?);
+
+            let info = gog::read_game_info(&install_path, app_name);
+            let title = info.as_ref().map(|i| i.title.clone()).unwrap_or_else(|| app_name.to_string());
+            let executable = info.and_then(|i| i.executable);
+
+            Some(HeroicGame {
+                app_name: app_name.to_string(),
+                title,
+                install_path,
+                executable,
+                store: HeroicStore::Gog,
+            })
+        })
+        .collect()
+}
+
+/// nile lists installs without titles; those are looked up in its
+/// `library.json`, and the executable in the game's `fuel.json`.
+fn parse_nile_installed(context: &ScanContext, content: &str, library: Option<&str>) -> Vec<HeroicGame> {
+    let Ok(Value::Array(installed)) = serde_json::from_str::<Value>(content) else {
+        return Vec::new();
+    };
+    let library: Vec<Value> = library
+        .and_then(|l| serde_json::from_str(l).ok())
+        .unwrap_or_default();
+
+    installed
+        .iter()
+        .filter_map(|game| {
+            let id = game.get("id")?.as_str()?;
+            let install_path = context.resolve(game.get("path")?.as_str()?);
+
+            let title = library.iter()
+                .find(|entry| entry.get("id").and_then(|v| v.as_str()) == Some(id))
+                .and_then(|entry| entry.get("product")?.get("title")?.as_str())
+                .unwrap_or(id)
+                .to_string();
+
+            let executable = std::fs::read_to_string(install_path.join("fuel.json"))
+                .ok()
+                .and_then(|c| serde_json::from_str::<Value>(&c).ok())
+                .and_then(|fuel| Some(fuel.get("Main")?.get("Command")?.as_str()?.to_string()))
+                .map(|exe| join_relative(&install_path, &exe));
+
+            Some(HeroicGame {
+                app_name: id.to_string(),
+                title,
+                install_path,
+                executable,
+                store: HeroicStore::Amazon,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture_dir() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join("heroic")
+    }
+
+    fn fixture_context() -> ScanContext {
+        ScanContext::offline(Platform::Linux, fixture_dir()).with_user_profile("/home/deck")
+    }
+
+    fn config_dir() -> PathBuf {
+        fixture_dir().join("home/deck/.config/heroic")
+    }
+
+    fn games_dir() -> PathBuf {
+        fixture_dir().join("home/deck/Games/Heroic")
+    }
+
+    fn read_config(relative: &str) -> String {
+        std::fs::read_to_string(config_dir().join(relative)).unwrap()
+    }
+
+    #[test]
+    fn parses_legendary_installs() {
+        let games = parse_legendary_installed(&fixture_context(), &read_config("legendaryConfig/legendary/installed.json"));
+        assert_eq!(games.len(), 1);
+
+        let game = &games[0];
+        assert_eq!(game.app_name, "Min");
+        assert_eq!(game.title, "Hades");
+        assert_eq!(game.store, HeroicStore::Epic);
+        assert_eq!(game.install_path, games_dir().join("Hades"));
+        assert_eq!(game.executable, Some(games_dir().join("Hades").join("x64").join("Hades.exe")));
+    }
+
+    #[test]
+    fn parses_gog_installs_with_info_files() {
+        let games = parse_gog_installed(&fixture_context(), &read_config("gog_store/installed.json"));
+        assert_eq!(games.len(), 2);
+
+        assert_eq!(games[0].title, "Cyberpunk 2077");
+        assert_eq!(games[0].store, HeroicStore::Gog);
+        assert_eq!(
+            games[0].executable,
+            Some(games_dir().join("Cyberpunk 2077").join("bin").join("x64").join("Cyberpunk2077.exe"))
+        );
+
+        // No .info file on disk: the app name stands in for the title.
+        assert_eq!(games[1].title, "1207658930");
+        assert_eq!(games[1].executable, None);
+    }
+
+    #[test]
+    fn parses_nile_installs_with_library_titles() {
+        let games = parse_nile_installed(
+            &fixture_context(),
+            &read_config("nile_config/nile/installed.json"),
+            Some(&read_config("nile_config/nile/library.json")),
+        );
+        assert_eq!(games.len(), 1);
+
+        let game = &games[0];
+        assert_eq!(game.title, "Fallout 76");
+        assert_eq!(game.store, HeroicStore::Amazon);
+        assert_eq!(game.executable, Some(games_dir().join("Fallout 76").join("Fallout76.exe")));
+    }
+
+    #[test]
+    fn discovers_every_backend_under_a_scan_root() {
+        let mut games = discover_heroic_games(&fixture_context());
+        games.sort_by(|a, b| a.title.cmp(&b.title));
+
+        let found: Vec<(&str, &Path)> = games.iter().map(|g| (g.title.as_str(), g.executables[0].as_path())).collect();
+        assert_eq!(
+            found,
+            vec![
+                ("Cyberpunk 2077", games_dir().join("Cyberpunk 2077/bin/x64/Cyberpunk2077.exe").as_path()),
+                ("Fallout 76", games_dir().join("Fallout 76/Fallout76.exe").as_path()),
+                ("Hades", games_dir().join("Hades/x64/Hades.exe").as_path()),
+            ]
+        );
+        assert_eq!(games[1].install_dir, Some(games_dir().join("Fallout 76")));
+
+        let sources: Vec<&str> = games.iter().map(|g| g.source.as_str()).collect();
+        assert_eq!(sources, vec!["heroic-gog", "heroic-amazon", "heroic-epic"]);
+    }
+}
@@ -0,0 +1,239 @@
+use rusqlite::{Connection, OpenFlags};
+use serde_yaml::Value;
+use std::path::{Path, PathBuf};
+use crate::detectors::context::ScanContext;
+use crate::detectors::{GameDetector, Platform};
+use crate::models::{DetectedGame, DetectionConfidence};
+
+pub struct LutrisDetector;
+
+impl GameDetector for LutrisDetector {
+    fn id(&self) -> &'static str {
+        "lutris"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Lutris"
+    }
+
+    fn platforms(&self) -> &'static [Platform] {
+        &[Platform::Linux]
+    }
+
+    fn sources(&self, context: &ScanContext) -> Vec<PathBuf> {
+        get_lutris_dirs(context)
+            .into_iter()
+            .flat_map(|(data_dir, config_dirs)| std::iter::once(data_dir.join("pga.db")).chain(config_dirs))
+            .collect()
+    }
+
+    fn discover(&self, context: &ScanContext) -> Result<Vec<DetectedGame>, Box<dyn std::error::Error>> {
+        Ok(discover_lutris_games(context))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LutrisGame {
+    pub slug: String,
+    pub name: String,
+    pub runner: String,
+    pub directory: Option<PathBuf>,
+    pub config_path: Option<String>,
+    pub executable: Option<PathBuf>,
+    /// Wine prefix set in the game config, for the `wine` runner.
+    pub prefix: Option<PathBuf>,
+}
+
+pub fn discover_lutris_games(context: &ScanContext) -> Vec<DetectedGame> {
+    let mut games: Vec<DetectedGame> = Vec::new();
+
+    for (data_dir, config_dirs) in get_lutris_dirs(context) {
+        let db_path = data_dir.join("pga.db");
+        if !db_path.exists() {
+            continue;
+        }
+
+        match find_lutris_games(&db_path, &config_dirs) {
+            Ok(found) => {
+                for game in found {
+                    let Some(executable) = game.executable else {
+                        continue;
+                    };
+                    if games.iter().any(|g| g.store_id.as_deref() == Some(game.slug.as_str())) {
+                        continue;
+                    }
+                    let title = if game.name.is_empty() { game.slug.clone() } else { game.name };
+                    games.push(DetectedGame {
+                        source: "lutris".to_string(),
+                        store_id: Some(game.slug),
+                        title,
+                        install_dir: game.directory.map(|dir| context.resolve(dir)),
+                        executables: vec![context.resolve(executable)],
+                        confidence: DetectionConfidence::High,
+                    });
+                }
+            }
+            Err(e) => eprintln!("⚠️ Failed to read {}: {}", db_path.display(), e),
+        }
+    }
+
+    println!("-> Total {} Lutris games cached.", games.len());
+    games
+}
+
+/// Returns each Lutris data directory with the directories its game YAML
+/// configs may live in (moved from the config dir to the data dir in 0.5.13).
+fn get_lutris_dirs(context: &ScanContext) -> Vec<(PathBuf, Vec<PathBuf>)> {
+    let mut dirs = Vec::new();
+
+    // The XDG overrides belong to the scanned user, so they only apply to
+    // the first profile.
+    for (index, home) in context.user_profiles().into_iter().enumerate() {
+        let xdg_dir = |name: &str| context.env_path(name).filter(|_| index == 0);
+        let data_home = xdg_dir("XDG_DATA_HOME").unwrap_or_else(|| home.join(".local").join("share"));
+        let config_home = xdg_dir("XDG_CONFIG_HOME").unwrap_or_else(|| home.join(".config"));
+        let flatpak = home.join(".var").join("app").join("net.lutris.Lutris");
+
+        dirs.push((data_home.join("lutris"), config_home.join("lutris")));
+        dirs.push((flatpak.join("data").join("lutris"), flatpak.join("config").join("lutris")));
+    }
+
+    dirs.into_iter()
+        .map(|(data, config)| {
+            let config_dirs = vec![data.join("games"), config.join("games")];
+            (data, config_dirs)
+        })
+        .collect()
+}
+
+pub fn find_lutris_games(db_path: &Path, config_dirs: &[PathBuf]) -> Result<Vec<LutrisGame>, Box<dyn std::error::Error>> {
+    let conn = Connection::open_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let mut games = read_installed_games(&conn)?;
+
+    for game in &mut games {
+        let Some(config_path) = &game.config_path else {
+            continue;
+        };
+
+        let Some(config) = config_dirs
+            .iter()
+            .map(|dir| dir.join(format!("{}.yml", config_path)))
+            .find_map(|path| std::fs::read_to_string(path).ok())
+        else {
+            continue;
+        };
+
+        if let Some(exe) = read_game_exe(&config) {
+            game.executable = Some(resolve_exe(&exe, game.directory.as_deref()));
+        }
+        game.prefix = read_game_prefix(&config).map(PathBuf::from);
+    }
+
+    Ok(games)
+}
+
+fn read_installed_games(conn: &Connection) -> rusqlite::Result<Vec<LutrisGame>> {
+    let mut stmt = conn.prepare(
+        "SELECT slug, name, runner, directory, configpath FROM games WHERE installed = 1",
+    )?;
+
+    let games = stmt
+        .query_map([], |row| {
+            let directory: Option<String> = row.get(3)?;
+            let config_path: Option<String> = row.get(4)?;
+            Ok(LutrisGame {
+                slug: row.get(0)?,
+                name: row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+                runner: row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+                directory: directory.filter(|d| !d.is_empty()).map(PathBuf::from),
+                config_path: config_path.filter(|c| !c.is_empty()),
+                executable: None,
+                prefix: None,
+            })
+        })?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    Ok(games)
+}
+
+/// Reads `game.exe` from a Lutris game config. Emulator runners use
+/// `main_file` for the ROM instead, which is not a process we can match.
+fn read_game_exe(config: &str) -> Option<String> {
+    let yaml: Value = serde_yaml::from_str(config).ok()?;
+    let exe = yaml.get("game")?.get("exe")?.as_str()?.trim();
+    (!exe.is_empty()).then(|| exe.to_string())
+}
+
+fn read_game_prefix(config: &str) -> Option<String> {
+    let yaml: Value = serde_yaml::from_str(config).ok()?;
+    let prefix = yaml.get("game")?.get("prefix")?.as_str()?.trim();
+    (!prefix.is_empty()).then(|| prefix.to_string())
+}
+
+/// Prefixes of the games Lutris runs with Wine. Games without a `prefix`
+/// setting use their game directory.
+pub fn get_wine_prefixes(context: &ScanContext) -> Vec<PathBuf> {
+    get_lutris_dirs(context)
+        .into_iter()
+        .filter(|(data_dir, _)| data_dir.join("pga.db").exists())
+        .filter_map(|(data_dir, config_dirs)| find_lutris_games(&data_dir.join("pga.db"), &config_dirs).ok())
+        .flatten()
+        .filter(|game| game.runner == "wine")
+        .filter_map(|game| game.prefix.or(game.directory))
+        .map(|prefix| context.resolve(prefix))
+        .collect()
+}
+
+fn resolve_exe(exe: &str, directory: Option<&Path>) -> PathBuf {
+    let path = PathBuf::from(exe);
+    match directory {
+        Some(dir) if path.is_relative() => dir.join(path),
+        _ => path,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINE_CONFIG: &str = include_str!("../../tests/fixtures/lutris/battle-net-1687431035.yml");
+
+    #[test]
+    fn reads_installed_games_from_pga_db() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE games (id INTEGER PRIMARY KEY, name TEXT, slug TEXT, runner TEXT,
+                 directory TEXT, installed INTEGER, configpath TEXT);
+             INSERT INTO games (name, slug, runner, directory, installed, configpath) VALUES
+                 ('Battle.net', 'battlenet', 'wine', '/home/deck/Games/battlenet', 1, 'battle-net-1687431035'),
+                 ('Celeste', 'celeste', 'linux', '', 1, NULL),
+                 ('Removed', 'removed', 'wine', '/tmp/removed', 0, 'removed-1');",
+        )
+        .unwrap();
+
+        let games = read_installed_games(&conn).unwrap();
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].slug, "battlenet");
+        assert_eq!(games[0].runner, "wine");
+        assert_eq!(games[0].directory, Some(PathBuf::from("/home/deck/Games/battlenet")));
+        assert_eq!(games[0].config_path.as_deref(), Some("battle-net-1687431035"));
+        assert_eq!(games[1].directory, None);
+    }
+
+    #[test]
+    fn reads_exe_from_game_config() {
+        let exe = read_game_exe(WINE_CONFIG).unwrap();
+        assert_eq!(exe, "drive_c/Program Files (x86)/Battle.net/Battle.net Launcher.exe");
+
+        let resolved = resolve_exe(&exe, Some(Path::new("/home/deck/Games/battlenet")));
+        assert_eq!(
+            resolved,
+            PathBuf::from("/home/deck/Games/battlenet/drive_c/Program Files (x86)/Battle.net/Battle.net Launcher.exe")
+        );
+        assert_eq!(resolve_exe("/opt/game/run", None), PathBuf::from("/opt/game/run"));
+
+        assert_eq!(read_game_prefix(WINE_CONFIG).as_deref(), Some("/home/deck/Games/battlenet"));
+        assert_eq!(read_game_exe("game:\n  main_file: /roms/zelda.sfc\nsnes9x: {}\n"), None);
+    }
+}
@@ -1,45 +1,291 @@
 pub mod amazon;
+pub mod battlenet;
+pub mod context;
+pub mod ea;
 pub mod epic;
+pub mod exe_selection;
+pub mod gog;
+pub mod heroic;
+pub mod lutris;
 pub mod registry;
 pub mod steam;
 pub mod ubisoft;
+pub mod wine;
+#[cfg(target_os = "windows")]
 pub mod xbox;
 
-use std::collections::HashSet;
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use crate::cache::{self, LibraryCache};
+use crate::detectors::context::ScanContext;
+use crate::models::DetectedGame;
 
-const ENABLE_REGISTRY_SCAN: bool = false;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    Windows,
+    Linux,
+    MacOs,
+}
+
+impl Platform {
+    pub fn current() -> Self {
+        if cfg!(target_os = "windows") {
+            Platform::Windows
+        } else if cfg!(target_os = "macos") {
+            Platform::MacOs
+        } else {
+            Platform::Linux
+        }
+    }
+}
+
+/// A source of installed games, usually one launcher or store.
+pub trait GameDetector {
+    /// Stable identifier used to enable or disable the detector, e.g. `steam`.
+    fn id(&self) -> &'static str;
+
+    fn display_name(&self) -> &'static str;
+
+    fn platforms(&self) -> &'static [Platform];
+
+    /// Heuristic detectors that may report non-games are opt-in.
+    fn enabled_by_default(&self) -> bool {
+        true
+    }
+
+    /// Files and folders `discover` reads, such as manifests and launcher
+    /// databases. A cached result is reused while none of them changed;
+    /// detectors that list nothing always rescan.
+    fn sources(&self, _context: &ScanContext) -> Vec<PathBuf> {
+        Vec::new()
+    }
+
+    fn discover(&self, context: &ScanContext) -> Result<Vec<DetectedGame>, Box<dyn Error>>;
+}
+
+pub struct DetectorResult {
+    pub id: &'static str,
+    pub display_name: &'static str,
+    /// [`ScanContext::label`] of the machine or prefix that was scanned.
+    pub scope: String,
+    pub games: Result<Vec<DetectedGame>, Box<dyn Error>>,
+    /// True when the games came from the library cache.
+    pub cached: bool,
+}
+
+#[derive(Default)]
+pub struct DetectorRegistry {
+    detectors: Vec<Box<dyn GameDetector>>,
+    overrides: HashMap<String, bool>,
+}
+
+impl DetectorRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A registry holding every detector this crate ships.
+    pub fn with_default_detectors() -> Self {
+        let mut detectors = Self::new();
+        detectors.register(Box::new(steam::SteamDetector));
+        detectors.register(Box::new(epic::EpicDetector));
+        detectors.register(Box::new(ubisoft::UbisoftDetector));
+        #[cfg(target_os = "windows")]
+        detectors.register(Box::new(xbox::XboxDetector));
+        detectors.register(Box::new(amazon::AmazonDetector));
+        detectors.register(Box::new(battlenet::BattlenetDetector));
+        detectors.register(Box::new(ea::EaDetector));
+        detectors.register(Box::new(gog::GogDetector));
+        detectors.register(Box::new(heroic::HeroicDetector));
+        detectors.register(Box::new(lutris::LutrisDetector));
+        detectors.register(Box::new(registry::RegistryDetector));
+        detectors
+    }
+
+    /// Adds a detector, replacing any registered under the same id.
+    pub fn register(&mut self, detector: Box<dyn GameDetector>) {
+        self.detectors.retain(|d| d.id() != detector.id());
+        self.detectors.push(detector);
+    }
+
+    /// Returns false if no detector with this id is registered.
+    pub fn set_enabled(&mut self, id: &str, enabled: bool) -> bool {
+        if !self.detectors.iter().any(|d| d.id() == id) {
+            return false;
+        }
+        self.overrides.insert(id.to_string(), enabled);
+        true
+    }
+
+    pub fn is_enabled(&self, id: &str) -> bool {
+        let Some(detector) = self.detectors.iter().find(|d| d.id() == id) else {
+            return false;
+        };
+        self.overrides.get(id).copied().unwrap_or_else(|| detector.enabled_by_default())
+    }
 
-pub fn scan_all_games() -> HashSet<String> {
-    println!("1. Preparing game list...");
+    pub fn detectors(&self) -> impl Iterator<Item = &dyn GameDetector> {
+        self.detectors.iter().map(|d| d.as_ref())
+    }
+
+    pub fn get(&self, id: &str) -> Option<&dyn GameDetector> {
+        self.detectors().find(|d| d.id() == id)
+    }
 
-    let steam_games = steam::discover_steam_games();
-    println!("🎮 Steam Games: {:?}", steam_games);
+    /// Enabled detectors that support the scanned platform.
+    pub fn applicable(&self, context: &ScanContext) -> impl Iterator<Item = &dyn GameDetector> {
+        let platform = context.platform();
+        self.detectors().filter(move |d| self.is_enabled(d.id()) && d.platforms().contains(&platform))
+    }
+
+    /// Runs every enabled detector that supports the scanned platform.
+    pub fn run(&self, context: &ScanContext, cache: &mut LibraryCache) -> Vec<DetectorResult> {
+        self.applicable(context).map(|d| run_detector(d, context, cache)).collect()
+    }
+}
 
-    let epic_games = epic::discover_epic_games();
-    println!("🎮 Epic Games: {:?}", epic_games);
+/// Runs one detector, reusing its cached games while its sources are
+/// unchanged.
+pub fn run_detector(detector: &dyn GameDetector, context: &ScanContext, cache: &mut LibraryCache) -> DetectorResult {
+    let sources = detector.sources(context);
+    let fingerprint = (!sources.is_empty()).then(|| cache::fingerprint(&sources));
+    let result = |games, cached| DetectorResult {
+        id: detector.id(),
+        display_name: detector.display_name(),
+        scope: context.label().to_string(),
+        games,
+        cached,
+    };
 
-    let ubisoft_games = ubisoft::discover_ubisoft_games();
-    println!("🎮 Ubisoft Games: {:?}", ubisoft_games);
+    if let Some(fingerprint) = &fingerprint
+        && let Some(games) = cache.get(detector.id(), context.label(), fingerprint)
+    {
+        return result(Ok(games.to_vec()), true);
+    }
 
-    let xbox_games = xbox::discover_xbox_games();
-    println!("🎮 Xbox/Store Games: {:?}", xbox_games);
+    let games = detector.discover(context);
+    if let (Ok(games), Some(fingerprint)) = (&games, fingerprint) {
+        cache.insert(detector.id(), context.label(), fingerprint, games.clone());
+    }
+    result(games, false)
+}
 
-    let amazon_games = amazon::discover_amazon_games();
-    println!("🎮 Amazon Games: {:?}", amazon_games);
+/// Logs what a detector found, or why it failed, and returns the games.
+pub fn report_result(result: DetectorResult) -> Vec<DetectedGame> {
+    match result.games {
+        Ok(games) => {
+            let titles: Vec<&str> = games.iter().map(|g| g.title.as_str()).collect();
+            let cached = if result.cached { " (cached)" } else { "" };
+            println!("🎮 {} Games{}: {:?}", result.display_name, cached, titles);
+            games
+        }
+        Err(e) => {
+            eprintln!("⚠️ {} detector ({}) failed: {}", result.display_name, result.id, e);
+            Vec::new()
+        }
+    }
+}
 
-    let mut all_games = HashSet::new();
+/// Joins a launcher-relative path that may use either separator.
+pub fn join_relative(base: &Path, relative: &str) -> PathBuf {
+    relative
+        .split(['\\', '/'])
+        .filter(|part| !part.is_empty() && *part != ".")
+        .fold(base.to_path_buf(), |path, part| path.join(part))
+}
 
-    all_games.extend(steam_games.into_iter().map(|s| s.to_lowercase()));
-    all_games.extend(epic_games.into_iter().map(|s| s.to_lowercase()));
-    all_games.extend(ubisoft_games.into_iter().map(|s| s.to_lowercase()));
-    all_games.extend(xbox_games.into_iter().map(|s| s.to_lowercase()));
-    all_games.extend(amazon_games.into_iter().map(|s| s.to_lowercase()));
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use crate::models::DetectionConfidence;
 
-    if ENABLE_REGISTRY_SCAN {
-        let registry_games = registry::discover_registry_games();
-        println!("🎮 Registry Games: {:?}", registry_games);
-        all_games.extend(registry_games.into_iter().map(|s| s.to_lowercase()));
+    #[derive(Default)]
+    struct FakeDetector {
+        id: &'static str,
+        fails: bool,
+        sources: Vec<PathBuf>,
+        runs: Rc<Cell<usize>>,
     }
 
-    all_games
+    impl GameDetector for FakeDetector {
+        fn id(&self) -> &'static str {
+            self.id
+        }
+
+        fn display_name(&self) -> &'static str {
+            "Fake"
+        }
+
+        fn platforms(&self) -> &'static [Platform] {
+            &[Platform::Windows, Platform::Linux, Platform::MacOs]
+        }
+
+        fn sources(&self, _context: &ScanContext) -> Vec<PathBuf> {
+            self.sources.clone()
+        }
+
+        fn discover(&self, _context: &ScanContext) -> Result<Vec<DetectedGame>, Box<dyn Error>> {
+            self.runs.set(self.runs.get() + 1);
+            if self.fails {
+                return Err("launcher data missing".into());
+            }
+            Ok(vec![DetectedGame {
+                source: self.id.to_string(),
+                store_id: None,
+                title: self.id.to_string(),
+                install_dir: None,
+                executables: vec![PathBuf::from(format!("{}.exe", self.id))],
+                confidence: DetectionConfidence::High,
+            }])
+        }
+    }
+
+    #[test]
+    fn runs_enabled_detectors_and_reports_each_result() {
+        let mut registry = DetectorRegistry::new();
+        registry.register(Box::new(FakeDetector { id: "good", ..Default::default() }));
+        registry.register(Box::new(FakeDetector { id: "broken", fails: true, ..Default::default() }));
+        registry.register(Box::new(FakeDetector { id: "off", ..Default::default() }));
+        assert!(registry.set_enabled("off", false));
+        assert!(!registry.set_enabled("missing", true));
+
+        let context = ScanContext::offline(Platform::Linux, "/nonexistent");
+        let mut cache = LibraryCache::default();
+        let results = registry.run(&context, &mut cache);
+        let ids: Vec<&str> = results.iter().map(|r| r.id).collect();
+        assert_eq!(ids, vec!["good", "broken"]);
+        assert_eq!(results[0].games.as_ref().unwrap()[0].title, "good");
+        assert!(results[1].games.is_err());
+
+        let titles: Vec<String> = registry.run(&context, &mut cache).into_iter().flat_map(report_result).map(|g| g.title).collect();
+        assert_eq!(titles, vec!["good"]);
+    }
+
+    #[test]
+    fn reuses_cached_results_until_sources_change() {
+        let manifest = std::env::temp_dir().join(format!("detector_cache_{}.json", std::process::id()));
+        std::fs::write(&manifest, "{}").unwrap();
+
+        let runs = Rc::new(Cell::new(0));
+        let uncached_runs = Rc::new(Cell::new(0));
+        let mut registry = DetectorRegistry::new();
+        registry.register(Box::new(FakeDetector { id: "manifests", sources: vec![manifest.clone()], runs: runs.clone(), ..Default::default() }));
+        registry.register(Box::new(FakeDetector { id: "live", runs: uncached_runs.clone(), ..Default::default() }));
+
+        let context = ScanContext::offline(Platform::Linux, "/nonexistent");
+        let mut cache = LibraryCache::default();
+        let first = registry.run(&context, &mut cache);
+        let second = registry.run(&context, &mut cache);
+        assert_eq!((runs.get(), uncached_runs.get()), (1, 2));
+        assert!(!first[0].cached && second[0].cached && !second[1].cached);
+        assert_eq!(second[0].games.as_ref().unwrap()[0].title, "manifests");
+
+        std::fs::write(&manifest, "{\"changed\": true}").unwrap();
+        registry.run(&context, &mut cache);
+        std::fs::remove_file(&manifest).unwrap();
+        assert_eq!(runs.get(), 2);
+    }
 }
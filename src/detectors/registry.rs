@@ -1,7 +1,39 @@
-use std::collections::HashSet;
-use std::path::Path;
-use winreg::enums::*;
-use winreg::RegKey;
+use std::path::{Path, PathBuf};
+use crate::detectors::context::ScanContext;
+use crate::detectors::exe_selection::{self, GAME_ENGINE_FILES};
+use crate::detectors::{GameDetector, Platform};
+use crate::parsers::pe::{self, Subsystem};
+use crate::models::{DetectedGame, DetectionConfidence};
+use crate::registry::{self, RegistrySource, RootKey};
+
+pub struct RegistryDetector;
+
+impl GameDetector for RegistryDetector {
+    fn id(&self) -> &'static str {
+        "registry"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Registry"
+    }
+
+    fn platforms(&self) -> &'static [Platform] {
+        &[Platform::Windows]
+    }
+
+    fn enabled_by_default(&self) -> bool {
+        // Uninstall-key heuristics also match plenty of non-games.
+        false
+    }
+
+    fn sources(&self, context: &ScanContext) -> Vec<PathBuf> {
+        context.registry_files().to_vec()
+    }
+
+    fn discover(&self, context: &ScanContext) -> Result<Vec<DetectedGame>, Box<dyn std::error::Error>> {
+        Ok(discover_registry_games(context))
+    }
+}
 
 const KNOWN_GAME_PUBLISHERS: &[&str] = &[
     "ubisoft", "ea games", "electronic arts", "riot games", "rockstar games",
@@ -10,12 +42,6 @@ const KNOWN_GAME_PUBLISHERS: &[&str] = &[
     "cd projekt", "paradox", "devolver", "focus entertainment", "deep silver",
 ];
 
-const GAME_ENGINE_FILES: &[&str] = &[
-    "unityplayer.dll", "ue4prerequisites", "unrealengine", "cryengine",
-    "fmod.dll", "bink2w64.dll", "steam_api.dll", "steam_api64.dll",
-    "eossdk-win64-shipping.dll", "galaxydll.dll", "galaxy64.dll",
-];
-
 const BLACKLIST_APPS: &[&str] = &[
     "chrome", "firefox", "edge", "microsoft", "office", "visual studio",
     "discord", "spotify", "steam client", "epic games launcher", "adobe",
@@ -24,23 +50,23 @@ const BLACKLIST_APPS: &[&str] = &[
     "directx", "visual c++", "redistributable", ".net", "framework",
 ];
 
-pub fn discover_registry_games() -> HashSet<String> {
-    let mut games = HashSet::new();
+pub fn discover_registry_games(context: &ScanContext) -> Vec<DetectedGame> {
+    let mut games: Vec<DetectedGame> = Vec::new();
+    let registry = context.registry();
 
     let registry_paths = [
-        (HKEY_LOCAL_MACHINE, r"SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall"),
-        (HKEY_LOCAL_MACHINE, r"SOFTWARE\WOW6432Node\Microsoft\Windows\CurrentVersion\Uninstall"),
-        (HKEY_CURRENT_USER, r"SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall"),
+        (RootKey::LocalMachine, r"SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall"),
+        (RootKey::LocalMachine, r"SOFTWARE\WOW6432Node\Microsoft\Windows\CurrentVersion\Uninstall"),
+        (RootKey::CurrentUser, r"SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall"),
     ];
 
     for (root, path) in registry_paths.iter() {
-        if let Ok(key) = RegKey::predef(*root).open_subkey(path) {
-            for subkey_name in key.enum_keys().flatten() {
-                if let Ok(subkey) = key.open_subkey(&subkey_name) {
-                    if let Some(game_exe) = process_registry_entry(&subkey) {
-                        games.insert(game_exe);
-                    }
-                }
+        for subkey_name in registry.subkeys(*root, path) {
+            let entry = UninstallEntry { registry, root: *root, path: registry::join_key(path, &subkey_name) };
+            if let Some(game) = process_registry_entry(context, &subkey_name, &entry)
+                && !games.iter().any(|g| g.executables == game.executables)
+            {
+                games.push(game);
             }
         }
     }
@@ -49,22 +75,63 @@ pub fn discover_registry_games() -> HashSet<String> {
     games
 }
 
-fn process_registry_entry(key: &RegKey) -> Option<String> {
-    println!("Processing registry entry: {:?}", key);
-    let display_name: String = key.get_value("DisplayName").ok()?;
-    let install_location: String = key.get_value("InstallLocation").unwrap_or_default();
+/// One subkey of an `Uninstall` key.
+struct UninstallEntry<'a> {
+    registry: &'a dyn RegistrySource,
+    root: RootKey,
+    path: String,
+}
+
+impl UninstallEntry<'_> {
+    fn get(&self, name: &str) -> Option<String> {
+        self.registry.string(self.root, &self.path, name)
+    }
+}
+
+fn process_registry_entry(context: &ScanContext, key_name: &str, key: &UninstallEntry) -> Option<DetectedGame> {
+    println!("Processing registry entry: {}", key.path);
+    let display_name = key.get("DisplayName")?;
+    let install_location = key.get("InstallLocation").unwrap_or_default();
 println!("Display Name: {}", display_name);
 println!("Install Location: {}", install_location);
+    let install_location = context.resolve(&install_location).to_string_lossy().to_string();
+
+    let publisher = key.get("Publisher").unwrap_or_default();
 
-    let publisher: String = key.get_value("Publisher").unwrap_or_default();
+    // Entries without a publisher get a second chance with the company the
+    // executable names, so only rule out the others up front.
+    if !publisher.is_empty() && !is_game(&display_name, &install_location, &publisher) {
+        return None;
+    }
 
-    if !is_game(&display_name, &install_location, &publisher) {
+    let install_dirs = [Some(PathBuf::from(&install_location)), get_uninstaller_dir(key).map(|dir| context.resolve(dir))];
+    let (install_dir, exe_path) = install_dirs
+        .into_iter()
+        .flatten()
+        .filter(|dir| !dir.as_os_str().is_empty() && dir.exists())
+        .find_map(|dir| {
+            let exe_path = exe_selection::best_executable(&dir, Some(&display_name))?;
+            Some((dir, exe_path))
+        })?;
+
+    let pe_info = pe::read_file(&exe_path).ok();
+    if pe_info.as_ref().is_some_and(|info| info.subsystem == Subsystem::WindowsConsole) {
         return None;
     }
+    if publisher.is_empty() {
+        let company = pe_info.and_then(|info| info.version?.company_name).unwrap_or_default();
+        if !is_game(&display_name, &install_dir.to_string_lossy(), &company) {
+            return None;
+        }
+    }
 
-    find_game_executable(&install_location).or_else(|| {
-        let uninstall_string: String = key.get_value("UninstallString").ok()?;
-        extract_exe_from_uninstall(&uninstall_string)
+    Some(DetectedGame {
+        source: "registry".to_string(),
+        store_id: Some(key_name.to_string()),
+        title: display_name,
+        install_dir: Some(install_dir),
+        executables: vec![exe_path],
+        confidence: DetectionConfidence::Low,
     })
 }
 
@@ -105,51 +172,65 @@ fn has_game_engine_files(install_path: &str) -> bool {
     false
 }
 
-fn find_game_executable(install_path: &str) -> Option<String> {
-    if install_path.is_empty() {
-        return None;
-    }
+/// Uninstallers usually live in the game folder, so theirs is the next best
+/// guess when `InstallLocation` is missing.
+fn get_uninstaller_dir(key: &UninstallEntry) -> Option<PathBuf> {
+    let uninstall_string = key.get("UninstallString")?;
+    let cleaned = uninstall_string.trim_matches('"');
+    Path::new(cleaned).parent().map(|parent| parent.to_path_buf())
+}
 
-    let path = Path::new(install_path);
-    if !path.exists() {
-        return None;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::hive::{Hive, HiveRegistry};
+    use crate::registry::memory::MemoryRegistry;
+
+    fn fixture_dir() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures")
     }
 
-    let mut exes: Vec<(String, u64)> = Vec::new();
+    /// A Unity game the way the fixture registries describe it, under a
+    /// scratch directory standing in for `C:`.
+    fn create_system_drive(name: &str) -> (PathBuf, PathBuf) {
+        let root = std::env::temp_dir().join(format!("{}_{}", name, std::process::id()));
+        let game_dir = root.join("Games").join("Hollow Knight");
+        std::fs::create_dir_all(game_dir.join("hollow_knight_Data")).unwrap();
+        std::fs::copy(fixture_dir().join("pe").join("game.exe"), game_dir.join("hollow_knight.exe")).unwrap();
+        std::fs::write(game_dir.join("UnityPlayer.dll"), b"").unwrap();
+        std::fs::write(game_dir.join("unins000.exe"), b"").unwrap();
+        (root, game_dir)
+    }
 
-    if let Ok(entries) = std::fs::read_dir(path) {
-        for entry in entries.flatten() {
-            let entry_path = entry.path();
-            if entry_path.extension().and_then(|s| s.to_str()) == Some("exe") {
-                if let Ok(metadata) = entry.metadata() {
-                    let name = entry.file_name().to_string_lossy().to_string();
-                    let name_lower = name.to_lowercase();
-
-                    if !name_lower.contains("unins") 
-                        && !name_lower.contains("setup") 
-                        && !name_lower.contains("launcher")
-                        && !name_lower.contains("crash")
-                        && !name_lower.contains("helper")
-                        && !name_lower.contains("update")
-                        && !name_lower.contains("redist") {
-                        exes.push((name, metadata.len()));
-                    }
-                }
-            }
-        }
+    #[test]
+    fn detects_games_from_uninstall_entries() {
+        let (root, game_dir) = create_system_drive("registry_detector");
+        let registry = MemoryRegistry::load_reg_file(&fixture_dir().join("registry").join("uninstall.reg")).unwrap();
+        let context = ScanContext::offline(Platform::Windows, &root).with_registry(Box::new(registry));
+
+        let games = discover_registry_games(&context);
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(games.len(), 1);
+        assert_eq!(games[0].title, "Hollow Knight");
+        assert_eq!(games[0].store_id.as_deref(), Some("Hollow Knight_is1"));
+        assert_eq!(games[0].install_dir, Some(game_dir.clone()));
+        assert_eq!(games[0].executables, vec![game_dir.join("hollow_knight.exe")]);
+        assert_eq!(games[0].confidence, DetectionConfidence::Low);
     }
 
-    exes.sort_by(|a, b| b.1.cmp(&a.1));
-    exes.first().map(|(name, _)| name.clone())
-}
+    #[test]
+    fn detects_games_from_an_offline_hive() {
+        let (root, game_dir) = create_system_drive("registry_hive");
+        let mut registry = HiveRegistry::new();
+        registry.mount(RootKey::LocalMachine, "SOFTWARE", Hive::open(&fixture_dir().join("registry").join("SOFTWARE")).unwrap());
+        let context = ScanContext::offline(Platform::Windows, &root).with_registry(Box::new(registry));
 
-fn extract_exe_from_uninstall(uninstall_string: &str) -> Option<String> {
-    let cleaned = uninstall_string.trim_matches('"');
-    let path = Path::new(cleaned);
-    
-    if let Some(parent) = path.parent() {
-        return find_game_executable(parent.to_str()?);
+        let games = discover_registry_games(&context);
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(games.len(), 1);
+        assert_eq!(games[0].title, "Hollow Knight");
+        assert_eq!(games[0].executables, vec![game_dir.join("hollow_knight.exe")]);
     }
-    
-    None
 }
@@ -1,38 +1,127 @@
-use std::collections::HashSet;
-use std::path::Path;
-
-#[cfg(target_os = "windows")]
-fn get_steam_path() -> Option<String> {
-    use winreg::RegKey;
-    use winreg::enums::*;
-
-    let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
-    if let Ok(steam_key) = hklm.open_subkey(r"SOFTWARE\WOW6432Node\Valve\Steam") {
-        if let Ok(install_path) = steam_key.get_value::<String, _>("InstallPath") {
-            return Some(format!(r"{}\steamapps", install_path));
-        }
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use crate::parsers::appinfo::{self, LaunchEntry};
+use crate::parsers::vdf::{self, KeyValue};
+use crate::detectors::context::ScanContext;
+use crate::detectors::{exe_selection, join_relative, GameDetector, Platform};
+use crate::models::{DetectedGame, DetectionConfidence};
+use crate::registry::RootKey;
+
+pub struct SteamDetector;
+
+impl GameDetector for SteamDetector {
+    fn id(&self) -> &'static str {
+        "steam"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Steam"
+    }
+
+    fn platforms(&self) -> &'static [Platform] {
+        &[Platform::Windows, Platform::Linux]
+    }
+
+    fn sources(&self, context: &ScanContext) -> Vec<PathBuf> {
+        get_manifest_sources(context)
+    }
+
+    fn discover(&self, context: &ScanContext) -> Result<Vec<DetectedGame>, Box<dyn std::error::Error>> {
+        Ok(discover_steam_games(context))
+    }
+}
+
+fn get_steam_roots(context: &ScanContext) -> Vec<PathBuf> {
+    let mut roots = Vec::new();
+
+    let steam_key = r"SOFTWARE\WOW6432Node\Valve\Steam";
+    if let Some(install_path) = context.registry().string(RootKey::LocalMachine, steam_key, "InstallPath") {
+        roots.push(context.resolve(install_path));
+    }
+
+    roots.extend(default_steam_roots(context));
+    dedup_existing_paths(roots)
+}
+
+fn default_steam_roots(context: &ScanContext) -> Vec<PathBuf> {
+    if context.platform() == Platform::Windows {
+        let program_files = context.env_path_or("ProgramFiles(x86)", r"C:\Program Files (x86)");
+        return vec![program_files.join("Steam")];
+    }
+
+    let mut roots: Vec<PathBuf> = context.env_path("XDG_DATA_HOME").map(|data| data.join("Steam")).into_iter().collect();
+    for home in context.user_profiles() {
+        roots.extend([
+            home.join(".steam").join("steam"),
+            home.join(".steam").join("root"),
+            home.join(".local").join("share").join("Steam"),
+            home.join(".var").join("app").join("com.valvesoftware.Steam").join(".local").join("share").join("Steam"),
+        ]);
+    }
+    roots
+}
+
+/// Every file the library is read from: `libraryfolders.vdf`, `appinfo.vdf`,
+/// the `steamapps` folders and their app manifests.
+fn get_manifest_sources(context: &ScanContext) -> Vec<PathBuf> {
+    let mut sources = context.registry_files().to_vec();
+    let mut libraries = Vec::new();
+
+    for steam_root in get_steam_roots(context) {
+        sources.push(steam_root.join("steamapps").join("libraryfolders.vdf"));
+        sources.push(steam_root.join("appcache").join("appinfo.vdf"));
+        libraries.extend(get_steam_library_folders(context, &steam_root));
+    }
+    for library in dedup_libraries(libraries) {
+        sources.extend(get_app_manifests(&library));
+        sources.push(library.path);
     }
 
-    Some(r"C:\Program Files (x86)\Steam\steamapps".to_string())
+    sources
 }
 
-pub fn discover_steam_games() -> HashSet<String> {
-    let mut games = HashSet::new();
+/// Drops paths that do not exist and collapses symlinked duplicates such as
+/// `~/.steam/steam` -> `~/.local/share/Steam`. The first path of each is
+/// kept as given: canonical paths on Windows are `\\?\C:\...` verbatim
+/// paths, which match nothing else we compare against.
+fn dedup_existing_paths(paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut seen = HashSet::new();
+    paths
+        .into_iter()
+        .filter(|path| std::fs::canonicalize(path).is_ok_and(|canonical| seen.insert(canonical)))
+        .collect()
+}
 
-    let steam_path = get_steam_path()
-        .unwrap_or_else(|| r"C:\Program Files (x86)\Steam\steamapps".to_string());
+pub fn discover_steam_games(context: &ScanContext) -> Vec<DetectedGame> {
+    let mut games: Vec<DetectedGame> = Vec::new();
 
-    let library_folders = get_steam_library_folders(&steam_path);
+    let steam_roots = get_steam_roots(context);
+    let os_list = os_preference(context.platform());
 
-    for library_path in library_folders {
-        if let Ok(entries) = std::fs::read_dir(&library_path) {
-            for entry in entries.flatten() {
-                if entry.path().extension().and_then(|s| s.to_str()) == Some("acf") {
-                    let steam_common = Path::new(&library_path).join("common");
-                    if let Some(game_name) = parse_acf_file(&entry.path(), &steam_common) {
-                        games.insert(game_name);
-                    }
-                }
+    let mut library_folders = Vec::new();
+    let mut launch_entries = HashMap::new();
+    for steam_root in &steam_roots {
+        library_folders.extend(get_steam_library_folders(context, steam_root));
+        launch_entries.extend(load_launch_entries(steam_root));
+    }
+    let library_folders = dedup_libraries(library_folders);
+
+    // A game moved to another library can leave its old manifest behind;
+    // the one Steam updated last is the install in use.
+    let mut last_updated: HashMap<u32, u64> = HashMap::new();
+    for library in library_folders {
+        let steam_common = library.path.join("common");
+        for manifest_path in get_app_manifests(&library) {
+            let Some(manifest) = read_app_manifest(&manifest_path) else {
+                continue;
+            };
+            if last_updated.get(&manifest.appid).is_some_and(|&newest| newest >= manifest.last_updated) {
+                continue;
+            }
+            if let Some(game) = parse_acf_file(&manifest, &steam_common, &launch_entries, os_list) {
+                games.retain(|g| g.store_id != game.store_id);
+                last_updated.insert(manifest.appid, manifest.last_updated);
+                games.push(game);
             }
         }
     }
@@ -41,51 +130,238 @@ pub fn discover_steam_games() -> HashSet<String> {
     games
 }
 
-fn get_steam_library_folders(steam_path: &str) -> Vec<String> {
-    let mut folders = vec![steam_path.to_string()];
+/// Proton prefixes, `steamapps/compatdata/<appid>/pfx`, in every library.
+pub fn get_compat_prefixes(context: &ScanContext) -> Vec<PathBuf> {
+    let libraries = get_steam_roots(context)
+        .iter()
+        .flat_map(|steam_root| get_steam_library_folders(context, steam_root))
+        .collect();
 
-    let vdf_path = Path::new(steam_path).join("libraryfolders.vdf");
-    if let Ok(content) = std::fs::read_to_string(vdf_path) {
-        for line in content.lines() {
-            if line.contains("\"path\"") {
-                if let Some(path) = line.split('"').nth(3) {
-                    let library_path = format!(r"{}\steamapps", path.replace("\\\\", "\\"));
-                    folders.push(library_path);
-                }
-            }
-        }
+    dedup_libraries(libraries)
+        .into_iter()
+        .filter_map(|library| std::fs::read_dir(library.path.join("compatdata")).ok())
+        .flat_map(|entries| entries.flatten().map(|entry| entry.path().join("pfx")))
+        .collect()
+}
+
+struct SteamLibrary {
+    path: PathBuf,
+    apps: Vec<u32>,
+}
+
+struct AppManifest {
+    appid: u32,
+    name: String,
+    install_dir: String,
+    state_flags: u64,
+    /// Unix time Steam last updated the app.
+    last_updated: u64,
+}
+
+// StateFlags bit set once every depot of the app is on disk.
+const STATE_FULLY_INSTALLED: u64 = 4;
+
+fn get_steam_library_folders(context: &ScanContext, steam_root: &Path) -> Vec<SteamLibrary> {
+    let steamapps = steam_root.join("steamapps");
+    let mut folders = vec![SteamLibrary { path: steamapps.clone(), apps: Vec::new() }];
+
+    let vdf_path = steamapps.join("libraryfolders.vdf");
+    for library in read_library_folders(&vdf_path).unwrap_or_default() {
+        let path = context.resolve(&library.path).join("steamapps");
+        folders.push(SteamLibrary { path, apps: library.apps });
     }
-    // println!("Steam folders count: {}", folders.len());
-    // for f in &folders {
-    //     println!("Steam folder: {}", f);
-    // }
     folders
 }
 
-fn parse_acf_file(path: &Path, steam_common: &Path) -> Option<String> {
-    let content = std::fs::read_to_string(path).ok()?;
-    let install_dir = content.lines()
-        .find(|l| l.to_lowercase().contains("\"installdir\""))?
-        .split('"').nth(3)?;
-
-    let game_folder = steam_common.join(install_dir);
-
-    if let Ok(entries) = std::fs::read_dir(&game_folder) {
-        let mut exes: Vec<_> = entries
-            .flatten()
-            .filter(|e| e.path().extension().and_then(|s| s.to_str()) == Some("exe"))
-            .filter_map(|e| {
-                let size = e.metadata().ok()?.len();
-                let name = e.file_name().to_string_lossy().to_string();
-                Some((name, size))
-            })
+/// Merges libraries that resolve to the same directory, keeping the first
+/// entry's path as given and the app list from whichever entry has one.
+fn dedup_libraries(libraries: Vec<SteamLibrary>) -> Vec<SteamLibrary> {
+    let mut merged: Vec<(PathBuf, SteamLibrary)> = Vec::new();
+
+    for library in libraries {
+        let Ok(canonical) = std::fs::canonicalize(&library.path) else {
+            continue;
+        };
+
+        match merged.iter_mut().find(|(existing, _)| *existing == canonical) {
+            Some((_, existing)) if existing.apps.is_empty() => existing.apps = library.apps,
+            Some(_) => {}
+            None => merged.push((canonical, library)),
+        }
+    }
+
+    merged.into_iter().map(|(_, library)| library).collect()
+}
+
+fn read_library_folders(vdf_path: &Path) -> Option<Vec<SteamLibrary>> {
+    let content = std::fs::read_to_string(vdf_path).ok()?;
+    let root = vdf::parse(&content).ok()?;
+    let folders = root.get_block("libraryfolders")?;
+
+    let libraries = folders
+        .iter()
+        .filter_map(|(_, value)| match value {
+            // Older clients store the library path directly under its index.
+            KeyValue::String(path) => Some(SteamLibrary { path: PathBuf::from(path), apps: Vec::new() }),
+            KeyValue::Block(block) => Some(SteamLibrary {
+                path: PathBuf::from(block.get_str("path")?),
+                apps: block
+                    .get_block("apps")
+                    .map(|apps| apps.iter().filter_map(|(id, _)| id.parse().ok()).collect())
+                    .unwrap_or_default(),
+            }),
+        })
+        .collect();
+
+    Some(libraries)
+}
+
+fn get_app_manifests(library: &SteamLibrary) -> Vec<PathBuf> {
+    let library_path = library.path.as_path();
+
+    if !library.apps.is_empty() {
+        return library.apps
+            .iter()
+            .map(|appid| library_path.join(format!("appmanifest_{}.acf", appid)))
+            .filter(|path| path.exists())
             .collect();
+    }
+
+    std::fs::read_dir(library_path)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| p.extension().and_then(|s| s.to_str()) == Some("acf"))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn read_app_manifest(path: &Path) -> Option<AppManifest> {
+    let content = std::fs::read_to_string(path).ok()?;
+    let root = vdf::parse(&content).ok()?;
+    let state = root.get_block("AppState")?;
 
-        exes.sort_by(|a, b| b.1.cmp(&a.1));
+    Some(AppManifest {
+        appid: state.get_u64("appid")? as u32,
+        name: state.get_str("name").unwrap_or_default().to_string(),
+        install_dir: state.get_str("installdir")?.to_string(),
+        state_flags: state.get_u64("StateFlags").unwrap_or(STATE_FULLY_INSTALLED),
+        last_updated: state.get_u64("LastUpdated").unwrap_or_default(),
+    })
+}
+
+fn load_launch_entries(steam_root: &Path) -> HashMap<u32, Vec<LaunchEntry>> {
+    let Ok(data) = std::fs::read(steam_root.join("appcache").join("appinfo.vdf")) else {
+        return HashMap::new();
+    };
 
-        if let Some((exe_name, _)) = exes.first() {
-            return Some(exe_name.clone());
+    match appinfo::parse(&data) {
+        Ok(apps) => apps
+            .into_iter()
+            .map(|app| (app.appid, app.launch_entries()))
+            .filter(|(_, entries)| !entries.is_empty())
+            .collect(),
+        Err(e) => {
+            eprintln!("⚠️ Failed to parse appinfo.vdf: {}", e);
+            HashMap::new()
         }
     }
-    None
-}
\ No newline at end of file
+}
+
+/// Steam `oslist` values runnable on `platform`, most preferred first.
+/// Linux runs Windows builds through Proton, so those are accepted after
+/// native ones.
+fn os_preference(platform: Platform) -> &'static [&'static str] {
+    match platform {
+        Platform::Windows => &["windows"],
+        Platform::Linux => &["linux", "windows"],
+        Platform::MacOs => &["macos"],
+    }
+}
+
+fn resolve_launch_executable(entries: &[LaunchEntry], game_folder: &Path, os_list: &[&str]) -> Option<PathBuf> {
+    let mut candidates: Vec<(usize, &LaunchEntry)> = entries
+        .iter()
+        .filter_map(|entry| {
+            let os_rank = os_list.iter().position(|os| entry.supports_os(os))?;
+            Some((os_rank, entry))
+        })
+        .collect();
+    candidates.sort_by_key(|(os_rank, entry)| (*os_rank, !entry.is_default()));
+    let candidates = candidates.into_iter().map(|(_, entry)| entry);
+
+    candidates.into_iter().find_map(|entry| {
+        let exe_path = join_relative(game_folder, &entry.executable);
+        exe_path.is_file().then_some(exe_path)
+    })
+}
+
+fn parse_acf_file(
+    manifest: &AppManifest,
+    steam_common: &Path,
+    launch_entries: &HashMap<u32, Vec<LaunchEntry>>,
+    os_list: &[&str],
+) -> Option<DetectedGame> {
+    if manifest.state_flags & STATE_FULLY_INSTALLED == 0 {
+        return None;
+    }
+
+    let game_folder = steam_common.join(&manifest.install_dir);
+    let title = if manifest.name.is_empty() { &manifest.install_dir } else { &manifest.name };
+    let game = |executable: PathBuf, confidence| DetectedGame {
+        source: "steam".to_string(),
+        store_id: Some(manifest.appid.to_string()),
+        title: title.clone(),
+        install_dir: Some(game_folder.clone()),
+        executables: vec![executable],
+        confidence,
+    };
+
+    if let Some(entries) = launch_entries.get(&manifest.appid)
+        && let Some(exe_path) = resolve_launch_executable(entries, &game_folder, os_list)
+    {
+        return Some(game(exe_path, DetectionConfidence::High));
+    }
+
+    exe_selection::best_executable(&game_folder, Some(title.as_str()))
+        .map(|exe_path| game(exe_path, DetectionConfidence::Medium))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_app_manifests() {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/steam/appmanifest_292030.acf");
+        let manifest = read_app_manifest(&path).unwrap();
+
+        assert_eq!(manifest.appid, 292030);
+        assert_eq!(manifest.install_dir, "The Witcher 3");
+        assert_eq!(manifest.state_flags & STATE_FULLY_INSTALLED, STATE_FULLY_INSTALLED);
+        assert_eq!(manifest.last_updated, 1700000000);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn merges_symlinked_libraries_under_their_given_path() {
+        let dir = std::env::temp_dir().join(format!("steam_libraries_{}", std::process::id()));
+        let real = dir.join("Steam").join("steamapps");
+        std::fs::create_dir_all(&real).unwrap();
+        std::os::unix::fs::symlink(dir.join("Steam"), dir.join("steam")).unwrap();
+        let linked = dir.join("steam").join("steamapps");
+
+        let libraries = dedup_libraries(vec![
+            SteamLibrary { path: linked.clone(), apps: Vec::new() },
+            SteamLibrary { path: real, apps: vec![292030] },
+            SteamLibrary { path: dir.join("missing"), apps: Vec::new() },
+        ]);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(libraries.len(), 1);
+        assert_eq!(libraries[0].path, linked);
+        assert_eq!(libraries[0].apps, vec![292030]);
+    }
+}
@@ -1,31 +1,69 @@
-use std::collections::HashSet;
-use std::path::Path;
-use winreg::enums::*;
-use winreg::RegKey;
-
-pub fn discover_ubisoft_games() -> HashSet<String> {
-    let mut games = HashSet::new();
-
-    #[cfg(target_os = "windows")]
-    {
-        let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
-
-        let registry_paths = vec![
-            r"SOFTWARE\Ubisoft\Launcher\Installs",
-            r"SOFTWARE\WOW6432Node\Ubisoft\Launcher\Installs",
-        ];
-
-        for reg_path in registry_paths {
-            if let Ok(installs_key) = hklm.open_subkey(reg_path) {
-                for install_id in installs_key.enum_keys().flatten() {
-                    if let Ok(game_key) = installs_key.open_subkey(&install_id) {
-                        if let Ok(install_dir) = game_key.get_value::<String, _>("InstallDir") {
-                            if let Some(exe_name) = find_largest_exe_in_dir(&install_dir) {
-                                games.insert(exe_name);
-                            }
-                        }
-                    }
-                }
+use std::path::PathBuf;
+use crate::detectors::context::ScanContext;
+use crate::detectors::{exe_selection, GameDetector, Platform};
+use crate::models::{DetectedGame, DetectionConfidence};
+use crate::registry::{self, RootKey};
+
+pub struct UbisoftDetector;
+
+impl GameDetector for UbisoftDetector {
+    fn id(&self) -> &'static str {
+        "ubisoft"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Ubisoft"
+    }
+
+    fn platforms(&self) -> &'static [Platform] {
+        &[Platform::Windows]
+    }
+
+    fn sources(&self, context: &ScanContext) -> Vec<PathBuf> {
+        context.registry_files().to_vec()
+    }
+
+    fn discover(&self, context: &ScanContext) -> Result<Vec<DetectedGame>, Box<dyn std::error::Error>> {
+        Ok(discover_ubisoft_games(context))
+    }
+}
+
+pub fn discover_ubisoft_games(context: &ScanContext) -> Vec<DetectedGame> {
+    let mut games: Vec<DetectedGame> = Vec::new();
+    let registry = context.registry();
+
+    let registry_paths = [
+        r"SOFTWARE\Ubisoft\Launcher\Installs",
+        r"SOFTWARE\WOW6432Node\Ubisoft\Launcher\Installs",
+    ];
+
+    for reg_path in registry_paths {
+        for install_id in registry.subkeys(RootKey::LocalMachine, reg_path) {
+            let game_key = registry::join_key(reg_path, &install_id);
+            let Some(install_dir) = registry.string(RootKey::LocalMachine, &game_key, "InstallDir") else {
+                continue;
+            };
+            if games.iter().any(|g| g.store_id.as_deref() == Some(install_id.as_str())) {
+                continue;
+            }
+
+            let title = install_dir
+                .trim_end_matches(['\\', '/'])
+                .rsplit(['\\', '/'])
+                .next()
+                .filter(|name| !name.is_empty())
+                .unwrap_or(&install_id)
+                .to_string();
+            let install_path = context.resolve(&install_dir);
+            if let Some(exe_path) = exe_selection::best_executable(&install_path, Some(&title)) {
+                games.push(DetectedGame {
+                    source: "ubisoft".to_string(),
+                    store_id: Some(install_id.clone()),
+                    title,
+                    install_dir: Some(install_path),
+                    executables: vec![exe_path],
+                    confidence: DetectionConfidence::Medium,
+                });
             }
         }
     }
@@ -34,35 +72,60 @@ pub fn discover_ubisoft_games() -> HashSet<String> {
     games
 }
 
-fn find_largest_exe_in_dir(dir_path: &str) -> Option<String> {
-    let path = Path::new(dir_path);
-    if !path.exists() {
-        return None;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use crate::detectors::Platform;
+    use crate::registry::RegValue;
+    use crate::registry::hive::{Hive, HiveRegistry};
+    use crate::registry::memory::MemoryRegistry;
+
+    fn create_install(dir: &std::path::Path, exe: &str) {
+        std::fs::create_dir_all(dir).unwrap();
+        std::fs::File::create(dir.join(exe)).unwrap().set_len(40 << 20).unwrap();
+        std::fs::File::create(dir.join("UbisoftGameLauncher.exe")).unwrap().set_len(20 << 20).unwrap();
     }
 
-    let excluded_patterns = [
-        "_ds", "dedicated", "server",
-        "launcher", "setup", "install", "unins",
-        "crash", "reporter", "redist", "prerequisite",
-        "update", "patcher", "config"
-    ];
+    #[test]
+    fn finds_installs_listed_in_the_registry() {
+        let root = std::env::temp_dir().join(format!("ubisoft_{}", std::process::id()));
+        let install_dir = root.join("Games").join("Far Cry 6");
+        create_install(&install_dir, "FarCry6.exe");
 
-    let mut exes: Vec<(String, u64)> = std::fs::read_dir(path).ok()?
-        .flatten()
-        .filter(|e| e.path().extension().and_then(|s| s.to_str()) == Some("exe"))
-        .filter_map(|e| {
-            let name = e.file_name().to_string_lossy().to_string();
-            let name_lower = name.to_lowercase();
+        let mut registry = MemoryRegistry::new();
+        let key = r"SOFTWARE\WOW6432Node\Ubisoft\Launcher\Installs\5266";
+        registry.set_value(RootKey::LocalMachine, key, "InstallDir", RegValue::String(r"C:\Games\Far Cry 6\".to_string()));
+        registry.create_key(RootKey::LocalMachine, r"SOFTWARE\Ubisoft\Launcher\Installs\404");
+        let context = ScanContext::offline(Platform::Windows, &root).with_registry(Box::new(registry));
 
-            if excluded_patterns.iter().any(|&pattern| name_lower.contains(pattern)) {
-                return None;
-            }
+        let games = discover_ubisoft_games(&context);
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(games.len(), 1);
+        assert_eq!(games[0].store_id.as_deref(), Some("5266"));
+        assert_eq!(games[0].title, "Far Cry 6");
+        assert_eq!(games[0].executables, vec![install_dir.join("FarCry6.exe")]);
+    }
 
-            let size = e.metadata().ok()?.len();
-            Some((name, size))
-        })
-        .collect();
+    #[test]
+    fn finds_installs_in_an_offline_hive() {
+        let fixtures = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures");
+        let games_drive = std::env::temp_dir().join(format!("ubisoft_hive_{}", std::process::id()));
+        create_install(&games_drive.join("Games").join("Far Cry 6"), "FarCry6.exe");
 
-    exes.sort_by(|a, b| b.1.cmp(&a.1));
-    exes.first().map(|(name, _)| name.clone())
+        let mut registry = HiveRegistry::new();
+        registry.mount(RootKey::LocalMachine, "SOFTWARE", Hive::open(&fixtures.join("registry").join("SOFTWARE")).unwrap());
+        let context = ScanContext::offline(Platform::Windows, "/nonexistent")
+            .with_drive('D', &games_drive)
+            .with_registry(Box::new(registry));
+
+        let games = discover_ubisoft_games(&context);
+        std::fs::remove_dir_all(&games_drive).unwrap();
+
+        // Assassin's Creed Odyssey is listed but not on disk.
+        assert_eq!(games.len(), 1);
+        assert_eq!(games[0].title, "Far Cry 6");
+        assert_eq!(games[0].install_dir, Some(games_drive.join("Games").join("Far Cry 6")));
+    }
 }
@@ -0,0 +1,171 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use crate::detectors::context::ScanContext;
+use crate::detectors::{heroic, lutris, steam, Platform};
+
+/// Finds Wine and Proton prefixes: `WINEPREFIX`, `~/.wine`, game folders
+/// under `~/Games` (Lutris' default) and `~/Games/Heroic/Prefixes/default`,
+/// prefixes named in Lutris and Heroic game settings, and Steam's
+/// `compatdata`.
+pub fn find_prefixes(context: &ScanContext) -> Vec<PathBuf> {
+    let mut candidates: Vec<PathBuf> = context.env_path("WINEPREFIX").into_iter().collect();
+
+    for home in context.user_profiles() {
+        let games = home.join("Games");
+        candidates.push(home.join(".wine"));
+        candidates.extend(subdirectories(&games));
+        candidates.extend(subdirectories(&games.join("Heroic").join("Prefixes").join("default")));
+    }
+    candidates.extend(lutris::get_wine_prefixes(context));
+    candidates.extend(heroic::get_wine_prefixes(context));
+    candidates.extend(steam::get_compat_prefixes(context));
+
+    let mut seen = HashSet::new();
+    candidates
+        .into_iter()
+        .filter(|path| is_prefix(path))
+        .filter(|path| seen.insert(std::fs::canonicalize(path).unwrap_or_else(|_| path.clone())))
+        .collect()
+}
+
+/// A scan context for each prefix found on a Linux machine, for running
+/// the Windows detectors inside them. Other platforms have none.
+pub fn prefix_contexts(context: &ScanContext) -> Vec<ScanContext> {
+    if context.platform() != Platform::Linux {
+        return Vec::new();
+    }
+
+    find_prefixes(context)
+        .into_iter()
+        .filter_map(|prefix| match ScanContext::wine_prefix(&prefix) {
+            Ok(prefix_context) => Some(prefix_context),
+            Err(e) => {
+                eprintln!("⚠️ Skipping Wine prefix {}: {}", prefix.display(), e);
+                None
+            }
+        })
+        .collect()
+}
+
+pub fn is_prefix(path: &Path) -> bool {
+    path.join("system.reg").is_file() && path.join("drive_c").is_dir()
+}
+
+/// Drive letters other than `C:` that a prefix links to in `dosdevices`,
+/// usually `z:` for the host's `/`.
+pub fn get_drive_links(prefix: &Path) -> Vec<(char, PathBuf)> {
+    let dosdevices = prefix.join("dosdevices");
+    let Ok(entries) = std::fs::read_dir(&dosdevices) else {
+        return Vec::new();
+    };
+
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_lowercase();
+            // `d::` style entries are raw devices rather than folders.
+            let letter = name.strip_suffix(':').filter(|l| l.len() == 1)?.chars().next()?;
+            if letter == 'c' || !letter.is_ascii_alphabetic() {
+                return None;
+            }
+            let target = std::fs::read_link(entry.path()).ok()?;
+            Some((letter, dosdevices.join(target)))
+        })
+        .collect()
+}
+
+/// The account the prefix's programs run as: Proton uses `steamuser`,
+/// plain Wine the Linux user name.
+pub fn get_prefix_user(prefix: &Path) -> Option<String> {
+    let mut users: Vec<String> = std::fs::read_dir(prefix.join("drive_c").join("users"))
+        .ok()?
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| !name.eq_ignore_ascii_case("Public") && !name.eq_ignore_ascii_case("Default"))
+        .collect();
+    users.sort();
+    users.into_iter().next()
+}
+
+fn subdirectories(dir: &Path) -> Vec<PathBuf> {
+    std::fs::read_dir(dir)
+        .map(|entries| entries.flatten().map(|e| e.path()).filter(|p| p.is_dir()).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::cache::LibraryCache;
+    use crate::detectors::{ubisoft, DetectorRegistry};
+
+    fn fixture_prefix() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join("wine").join("prefix")
+    }
+
+    fn create_prefix(path: &Path) {
+        std::fs::create_dir_all(path.join("drive_c").join("users").join("steamuser")).unwrap();
+        std::fs::create_dir_all(path.join("drive_c").join("users").join("Public")).unwrap();
+        std::fs::create_dir_all(path.join("dosdevices")).unwrap();
+        std::os::unix::fs::symlink("/", path.join("dosdevices").join("z:")).unwrap();
+        std::os::unix::fs::symlink("../drive_c", path.join("dosdevices").join("c:")).unwrap();
+        for file in ["system.reg", "user.reg"] {
+            std::fs::copy(fixture_prefix().join(file), path.join(file)).unwrap();
+        }
+    }
+
+    #[test]
+    fn finds_prefixes_in_known_locations() {
+        let root = std::env::temp_dir().join(format!("wine_prefixes_{}", std::process::id()));
+        let home = root.join("home").join("deck");
+        create_prefix(&home.join(".wine"));
+        create_prefix(&home.join("Games").join("battlenet"));
+        create_prefix(&home.join(".local").join("share").join("Steam").join("steamapps").join("compatdata").join("2050650").join("pfx"));
+        std::fs::create_dir_all(home.join("Games").join("Celeste")).unwrap();
+
+        let context = ScanContext::offline(Platform::Linux, &root).with_user_profile("/home/deck");
+        let mut prefixes = find_prefixes(&context);
+        prefixes.sort();
+
+        assert_eq!(prefixes.len(), 3);
+        assert!(prefixes[0].ends_with(".local/share/Steam/steamapps/compatdata/2050650/pfx"));
+        assert_eq!(prefixes[1], home.join(".wine"));
+        assert_eq!(prefixes[2], home.join("Games").join("battlenet"));
+
+        assert_eq!(get_prefix_user(&prefixes[1]).as_deref(), Some("steamuser"));
+        assert_eq!(get_drive_links(&prefixes[1]), vec![('z', PathBuf::from("/"))]);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn runs_windows_detectors_inside_prefixes() {
+        let root = std::env::temp_dir().join(format!("wine_detectors_{}", std::process::id()));
+        let prefix = root.join("home").join("deck").join(".wine");
+        create_prefix(&prefix);
+        let game_dir = prefix
+            .join("drive_c")
+            .join("Program Files (x86)")
+            .join("Ubisoft")
+            .join("Ubisoft Game Launcher")
+            .join("games")
+            .join("Far Cry 6");
+        std::fs::create_dir_all(&game_dir).unwrap();
+        std::fs::File::create(game_dir.join("FarCry6.exe")).unwrap().set_len(40 << 20).unwrap();
+
+        let mut registry = DetectorRegistry::new();
+        registry.register(Box::new(ubisoft::UbisoftDetector));
+        let context = ScanContext::offline(Platform::Linux, &root).with_user_profile("/home/deck");
+        let prefixes = prefix_contexts(&context);
+        assert_eq!(prefixes.len(), 1);
+        assert!(registry.run(&context, &mut LibraryCache::default()).is_empty());
+        let results = registry.run(&prefixes[0], &mut LibraryCache::default());
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(results.len(), 1);
+        let games = results[0].games.as_ref().unwrap();
+        assert_eq!(games.len(), 1);
+        assert_eq!(games[0].title, "Far Cry 6");
+        assert_eq!(games[0].executables, vec![game_dir.join("FarCry6.exe")]);
+    }
+}
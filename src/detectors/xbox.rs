@@ -1,10 +1,42 @@
-use std::collections::HashSet;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use windows::ApplicationModel::Package;
 use windows::Management::Deployment::PackageManager;
+use crate::detectors::context::ScanContext;
+use crate::detectors::{exe_selection, join_relative, GameDetector, Platform};
+use crate::models::{DetectedGame, DetectionConfidence};
+use crate::parsers::pe;
 
-pub fn discover_xbox_games() -> HashSet<String> {
-    let mut games = HashSet::new();
+pub struct XboxDetector;
+
+impl GameDetector for XboxDetector {
+    fn id(&self) -> &'static str {
+        "xbox"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Xbox/Store"
+    }
+
+    fn platforms(&self) -> &'static [Platform] {
+        &[Platform::Windows]
+    }
+
+    fn sources(&self, context: &ScanContext) -> Vec<PathBuf> {
+        // Installing or removing a package adds or removes its data folder.
+        context.env_path("LOCALAPPDATA").map(|local| local.join("Packages")).into_iter().collect()
+    }
+
+    fn discover(&self, context: &ScanContext) -> Result<Vec<DetectedGame>, Box<dyn std::error::Error>> {
+        // Packages are only known to the running system's package manager.
+        if !context.is_live() {
+            return Ok(Vec::new());
+        }
+        Ok(discover_xbox_games())
+    }
+}
+
+pub fn discover_xbox_games() -> Vec<DetectedGame> {
+    let mut games = Vec::new();
     println!("🔍 Scanning for Xbox/Microsoft Store games...");
 
     let package_manager = match PackageManager::new() {
@@ -32,9 +64,9 @@ pub fn discover_xbox_games() -> HashSet<String> {
         };
 
         match check_if_game(&package, &display_name) {
-            Ok(Some(exe_name)) => {
-                // println!("✅ Found Xbox Game: {} (Exe: {})", display_name, exe_name);
-                games.insert(exe_name);
+            Ok(Some(game)) => {
+                // println!("✅ Found Xbox Game: {} (Exe: {:?})", display_name, game.executables);
+                games.push(game);
             }
             Ok(None) => {
                 // Not a game or system app
@@ -48,7 +80,7 @@ pub fn discover_xbox_games() -> HashSet<String> {
     games
 }
 
-fn check_if_game(package: &Package, name: &str) -> Result<Option<String>, String> {
+fn check_if_game(package: &Package, name: &str) -> Result<Option<DetectedGame>, String> {
     let installed_location = package.InstalledLocation()
         .map_err(|_| "No installed location access".to_string())?;
 
@@ -56,13 +88,22 @@ fn check_if_game(package: &Package, name: &str) -> Result<Option<String>, String
         .map_err(|_| "Failed to get path string".to_string())?;
 
     let path = PathBuf::from(path_hstring.to_string());
+    let family_name = package.Id().and_then(|id| id.FamilyName()).ok().map(|n| n.to_string());
+    let detected = |(exe_path, confidence): (PathBuf, DetectionConfidence)| DetectedGame {
+        source: "xbox".to_string(),
+        store_id: family_name.clone(),
+        title: resolve_title(name, &exe_path),
+        install_dir: Some(path.clone()),
+        executables: vec![exe_path],
+        confidence,
+    };
 
     // Priority: If MicrosoftGame.config exists, it's a game
     let game_config = path.join("MicrosoftGame.config");
-    if game_config.exists() {
-        if let Some(exe) = find_real_game_exe(&path, name) {
-            return Ok(Some(exe));
-        }
+    if game_config.exists()
+        && let Some(exe) = find_real_game_exe(&path, name)
+    {
+        return Ok(Some(detected(exe)));
     }
 
     // Manifest control (old UWP games)
@@ -77,61 +118,40 @@ fn check_if_game(package: &Package, name: &str) -> Result<Option<String>, String
     let is_game = content.contains("Category=\"windows.game\"") ||
                   content.contains("uap3:GameMode");
 
-    if is_game {
-        if let Some(exe) = find_real_game_exe(&path, name) {
-            return Ok(Some(exe));
-        }
+    if is_game
+        && let Some(exe) = find_real_game_exe(&path, name)
+    {
+        return Ok(Some(detected(exe)));
     }
 
     Ok(None)
 }
-fn find_real_game_exe(package_path: &PathBuf, game_name: &str) -> Option<String> {
+fn find_real_game_exe(package_path: &PathBuf, game_name: &str) -> Option<(PathBuf, DetectionConfidence)> {
     let config_path = package_path.join("MicrosoftGame.config");
     if config_path.exists() {
         if let Ok(content) = std::fs::read_to_string(&config_path) {
             if let Some(start) = content.find("<ExecutableName>") {
                 let rest = &content[start + 16..];
                 if let Some(end) = rest.find("</ExecutableName>") {
-                    return Some(rest[..end].to_string());
+                    return Some((join_relative(package_path, &rest[..end]), DetectionConfidence::High));
                 }
             }
         }
     }
 
-    let skip_keywords = ["launcher", "helper", "crash", "server", "unity", "report", "redist"];
-    let game_name_lower = game_name.to_lowercase().replace(" ", "");
-    
-    let mut candidates: Vec<(String, u64)> = Vec::new();
-    
-    for entry in walkdir::WalkDir::new(package_path)
-        .max_depth(5)
-        .into_iter()
-        .filter_map(|e| e.ok())
-    {
-        let path = entry.path();
-        if path.extension().map_or(false, |e| e == "exe") {
-            if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                let name_lower = name.to_lowercase();
-                
-                if skip_keywords.iter().any(|kw| name_lower.contains(kw)) {
-                    continue;
-                }
-                
-                if let Ok(meta) = std::fs::metadata(path) {
-                    candidates.push((name.to_string(), meta.len()));
-                }
-            }
-        }
-    }
+    exe_selection::best_executable(package_path, Some(game_name))
+        .map(|path| (path, DetectionConfidence::Medium))
+}
 
-    if let Some((name, _)) = candidates.iter().find(|(n, _)| {
-        n.to_lowercase().replace(" ", "").contains(&game_name_lower)
-    }) {
-        return Some(name.clone());
+/// Packages whose display name did not resolve still carry one in the game
+/// binary's version resource.
+fn resolve_title(display_name: &str, exe_path: &Path) -> String {
+    if !display_name.is_empty() && display_name != "Unknown" && !display_name.starts_with("ms-resource:") {
+        return display_name.to_string();
     }
 
-    candidates.into_iter()
-        .filter(|(_, size)| *size > 10_000_000)
-        .max_by_key(|(_, size)| *size)
-        .map(|(name, _)| name)
-}
\ No newline at end of file
+    pe::read_file(exe_path)
+        .ok()
+        .and_then(|info| info.version?.product_name)
+        .unwrap_or_else(|| display_name.to_string())
+}
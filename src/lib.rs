@@ -0,0 +1,13 @@
+pub mod models;
+pub mod cache;
+pub mod library;
+pub mod detectors;
+pub mod parsers;
+pub mod tracker;
+pub mod storage;
+pub mod system;
+pub mod registry;
+pub mod watcher;
+pub mod monitor;
+#[cfg(target_os = "linux")]
+pub mod procfs;
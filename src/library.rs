@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+use std::path::Path;
+use crate::models::DetectedGame;
+
+/// The detected games, indexed by lowercase executable file name.
+#[derive(Debug, Clone, Default)]
+pub struct GameLibrary {
+    games: Vec<DetectedGame>,
+    by_executable: HashMap<String, Vec<usize>>,
+    /// Path keys of each game's executables and install folder, as listed
+    /// and with symlinks resolved.
+    executable_keys: Vec<Vec<PathKey>>,
+    install_keys: Vec<Vec<PathKey>>,
+}
+
+/// A path as lowercase components, so that Windows paths match regardless
+/// of case and separators.
+type PathKey = Vec<String>;
+
+impl GameLibrary {
+    pub fn new(games: Vec<DetectedGame>) -> Self {
+        let mut by_executable: HashMap<String, Vec<usize>> = HashMap::new();
+        for (index, game) in games.iter().enumerate() {
+            for name in game.executable_names() {
+                let entries = by_executable.entry(name).or_default();
+                if !entries.contains(&index) {
+                    entries.push(index);
+                }
+            }
+        }
+
+        let executable_keys = games.iter().map(|g| g.executables.iter().flat_map(|e| path_keys(e)).collect()).collect();
+        let install_keys = games.iter().map(|g| g.install_dir.iter().flat_map(|dir| path_keys(dir)).collect()).collect();
+
+        Self { games, by_executable, executable_keys, install_keys }
+    }
+
+    pub fn games(&self) -> &[DetectedGame] {
+        &self.games
+    }
+
+    /// Returns the most confident game shipping an executable with this name.
+    pub fn find_by_executable(&self, exe_name: &str) -> Option<&DetectedGame> {
+        self.by_executable
+            .get(&exe_name.to_lowercase())?
+            .iter()
+            .rev()
+            .map(|&index| &self.games[index])
+            .max_by_key(|game| game.confidence)
+    }
+
+    /// Like [`GameLibrary::find_by_executable`], but when the full path of
+    /// the program is known, only games it belongs to count: first those
+    /// listing that very file, then those whose install folder holds it.
+    /// Games without a known install folder still match by name.
+    pub fn find_by_path(&self, exe_name: &str, exe_path: Option<&Path>) -> Option<&DetectedGame> {
+        let Some(exe_path) = exe_path else {
+            return self.find_by_executable(exe_name);
+        };
+
+        let candidates = self.by_executable.get(&exe_name.to_lowercase())?;
+        let exe_keys = path_keys(exe_path);
+        let best = |matches: &dyn Fn(usize) -> bool| {
+            candidates
+                .iter()
+                .rev()
+                .filter(|&&index| matches(index))
+                .map(|&index| &self.games[index])
+                .max_by_key(|game| game.confidence)
+        };
+
+        best(&|index| self.executable_keys[index].iter().any(|key| exe_keys.contains(key)))
+            .or_else(|| best(&|index| self.install_keys[index].iter().any(|dir| exe_keys.iter().any(|exe| exe.starts_with(dir)))))
+            .or_else(|| best(&|index| self.games[index].install_dir.is_none()))
+    }
+
+    /// Returns the game whose install folder holds `path`, for programs a
+    /// game ships besides the executables detectors list, e.g. the
+    /// `-Shipping.exe` an Unreal bootstrapper starts. The innermost folder
+    /// wins when install folders nest.
+    pub fn find_by_install_dir(&self, path: &Path) -> Option<&DetectedGame> {
+        let keys = path_keys(path);
+        self.install_keys
+            .iter()
+            .enumerate()
+            .filter_map(|(index, dirs)| {
+                // A drive or filesystem root would claim every program.
+                dirs.iter()
+                    .filter(|dir| dir.len() > 1 && keys.iter().any(|key| key.len() > dir.len() && key.starts_with(dir)))
+                    .map(|dir| dir.len())
+                    .max()
+                    .map(|depth| (depth, self.games[index].confidence, index))
+            })
+            .max()
+            .map(|(_, _, index)| &self.games[index])
+    }
+
+    pub fn find_by_store_id(&self, source: &str, store_id: &str) -> Option<&DetectedGame> {
+        self.games
+            .iter()
+            .find(|game| game.source == source && game.store_id.as_deref() == Some(store_id))
+    }
+}
+
+/// Keys for `path` as written and, if it exists, with symlinks resolved,
+/// e.g. `~/.steam/steam` leading to `~/.local/share/Steam`.
+fn path_keys(path: &Path) -> Vec<PathKey> {
+    let mut keys = vec![path_key(path)];
+    if let Ok(canonical) = std::fs::canonicalize(path) {
+        let key = path_key(&canonical);
+        if key != keys[0] {
+            keys.push(key);
+        }
+    }
+    keys
+}
+
+fn path_key(path: &Path) -> PathKey {
+    path.to_string_lossy()
+        .split(['\\', '/'])
+        .filter(|part| !part.is_empty() && *part != ".")
+        .map(|part| part.to_lowercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::DetectionConfidence;
+    use std::path::PathBuf;
+
+    fn game(source: &str, store_id: &str, exe: &str, confidence: DetectionConfidence) -> DetectedGame {
+        DetectedGame {
+            source: source.to_string(),
+            store_id: Some(store_id.to_string()),
+            title: format!("{} {}", source, store_id),
+            install_dir: None,
+            executables: vec![PathBuf::from(exe)],
+            confidence,
+        }
+    }
+
+    #[test]
+    fn looks_up_games_by_executable_and_store_id() {
+        let library = GameLibrary::new(vec![
+            game("registry", "Witcher", "/games/witcher3/witcher3.exe", DetectionConfidence::Low),
+            game("gog", "1207664663", "/games/witcher3/bin/x64/Witcher3.exe", DetectionConfidence::High),
+            game("steam", "1091500", "/games/cp2077/Cyberpunk2077.exe", DetectionConfidence::Medium),
+        ]);
+
+        assert_eq!(library.find_by_executable("WITCHER3.EXE").unwrap().source, "gog");
+        assert_eq!(library.find_by_executable("cyberpunk2077.exe").unwrap().title, "steam 1091500");
+        assert!(library.find_by_executable("explorer.exe").is_none());
+
+        assert_eq!(library.find_by_store_id("steam", "1091500").unwrap().executables.len(), 1);
+        assert!(library.find_by_store_id("gog", "1091500").is_none());
+        assert_eq!(library.games().len(), 3);
+    }
+
+    #[test]
+    fn tells_apart_games_with_the_same_executable_name() {
+        let mut first = game("steam", "1", "/games/Alpha/game.exe", DetectionConfidence::High);
+        first.install_dir = Some(PathBuf::from("/games/Alpha"));
+        let mut second = game("gog", "2", "/mnt/c/Games/Beta/bin/game.exe", DetectionConfidence::Medium);
+        second.install_dir = Some(PathBuf::from("/mnt/c/Games/Beta"));
+        let library = GameLibrary::new(vec![first, second]);
+
+        let find = |path: &str| library.find_by_path("game.exe", Some(Path::new(path))).map(|g| g.source.as_str());
+        assert_eq!(find("/games/Alpha/game.exe"), Some("steam"));
+        assert_eq!(find("/mnt/c/games/beta/bin/GAME.EXE"), Some("gog"));
+        assert_eq!(find("/mnt/c/Games/Beta/tools/game.exe"), Some("gog"));
+        assert_eq!(find("/usr/bin/game.exe"), None);
+        assert_eq!(library.find_by_path("game.exe", None).unwrap().source, "steam");
+    }
+
+    #[test]
+    fn finds_games_by_install_folder() {
+        let mut outer = game("steam", "1", "/games/Suite/launcher.exe", DetectionConfidence::High);
+        outer.install_dir = Some(PathBuf::from("/games/Suite"));
+        let mut inner = game("steam", "2", "/games/Suite/Chapter2/chapter2.exe", DetectionConfidence::Medium);
+        inner.install_dir = Some(PathBuf::from("/games/Suite/Chapter2"));
+        let mut root = game("registry", "3", "/tool.exe", DetectionConfidence::Low);
+        root.install_dir = Some(PathBuf::from("/"));
+        let library = GameLibrary::new(vec![outer, inner, root]);
+
+        let find = |path: &str| library.find_by_install_dir(Path::new(path)).and_then(|g| g.store_id.as_deref());
+        assert_eq!(find("/games/Suite/Binaries/Win64/Suite-Win64-Shipping.exe"), Some("1"));
+        assert_eq!(find("/GAMES/suite/chapter2/bin/crash.exe"), Some("2"));
+        assert_eq!(find("/games/Suite"), None);
+        assert_eq!(find("/usr/bin/bash"), None);
+    }
+}
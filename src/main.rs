@@ -1,36 +1,123 @@
-mod models;
-mod detectors;
-mod tracker;
-mod storage;
-mod system;
-
-use std::thread;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
-use crate::tracker::SessionTracker;
-use crate::system::SystemScanner;
-use crate::storage::save_sessions_to_json;
-use crate::detectors::scan_all_games;
+use game_detection::tracker::SessionTracker;
+use game_detection::monitor::ProcessMonitor;
+use game_detection::storage::{append_session_events, save_sessions_to_json};
+use game_detection::cache::{LibraryCache, LIBRARY_CACHE_FILE};
+use game_detection::detectors::context::ScanContext;
+use game_detection::detectors::DetectorRegistry;
+use game_detection::watcher::LibraryWatcher;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let game_cache = scan_all_games();
+    let mut registry = DetectorRegistry::with_default_detectors();
+    let options = apply_cli_args(&mut registry, std::env::args().skip(1));
+
+    let context = match &options.windows_root {
+        Some(root) => ScanContext::windows_install(root, options.windows_user.as_deref())?,
+        None => ScanContext::live(),
+    };
+
+    let cache_path = Path::new(LIBRARY_CACHE_FILE);
+    let mut cache = if options.rescan { LibraryCache::default() } else { LibraryCache::load(cache_path) };
+    let mut library_watcher = LibraryWatcher::scan(&registry, context, &mut cache);
+    save_cache(&cache, cache_path);
 
-    println!("2. Session Tracking Started. (Writing to active_sessions.json)");
+    println!("2. Session Tracking Started. (Writing to active_sessions.json and session_events.jsonl)");
 
-    let mut session_tracker = SessionTracker::new(game_cache);
-    let system_scanner = SystemScanner::new()?;
+    let mut session_tracker = SessionTracker::new(library_watcher.games());
+    for (game, grace_period) in options.grace_periods {
+        match game {
+            Some(game) => session_tracker.set_game_grace_period(&game, grace_period),
+            None => session_tracker.set_grace_period(grace_period),
+        }
+    }
+    let process_monitor = ProcessMonitor::start();
 
     loop {
-        match system_scanner.get_running_processes() {
-            Ok(processes) => {
-                let active_sessions = session_tracker.update(&processes);
-                
-                if let Err(e) = save_sessions_to_json(active_sessions) {
-                    eprintln!("Error saving sessions: {}", e);
+        if !library_watcher.poll(&registry, &mut cache).is_empty() {
+            session_tracker.set_games(library_watcher.games());
+            save_cache(&cache, cache_path);
+        }
+
+        let events = process_monitor.wait(Duration::from_secs(1)).ok_or("process monitor stopped")?;
+        for event in &events {
+            session_tracker.handle_event(event);
+        }
+        if let Err(e) = save_sessions_to_json(session_tracker.refresh()) {
+            eprintln!("Error saving sessions: {}", e);
+        }
+
+        let session_events = session_tracker.take_events();
+        if !session_events.is_empty()
+            && let Err(e) = append_session_events(&session_events)
+        {
+            eprintln!("Error saving session events: {}", e);
+        }
+    }
+}
+
+fn save_cache(cache: &LibraryCache, path: &Path) {
+    if let Err(e) = cache.save(path) {
+        eprintln!("⚠️ Failed to save library cache: {}", e);
+    }
+}
+
+#[derive(Default)]
+struct CliOptions {
+    /// A mounted Windows partition to build the library from instead of
+    /// this machine, e.g. the Windows side of a dual-boot setup.
+    windows_root: Option<PathBuf>,
+    /// The account under `Users` whose launchers are read on that partition.
+    windows_user: Option<String>,
+    /// Ignore the library cache and run every detector.
+    rescan: bool,
+    /// How long a game may be gone before its session ends, for every game
+    /// or for the one with the given store id or executable. Defaults to 60
+    /// seconds, so a crash and relaunch stay one session; `--grace-period 0`
+    /// ends sessions as soon as the game's last process exits.
+    grace_periods: Vec<(Option<String>, chrono::Duration)>,
+}
+
+/// Handles `--enable <detector id>` and `--disable <detector id>`, and reads
+/// `--windows-root <path>`, `--windows-user <name>`, `--rescan` and
+/// `--grace-period [<store id or executable>=]<seconds>`.
+fn apply_cli_args(registry: &mut DetectorRegistry, mut args: impl Iterator<Item = String>) -> CliOptions {
+    let mut options = CliOptions::default();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--enable" | "--disable" => match args.next() {
+                Some(id) => {
+                    if !registry.set_enabled(&id, arg == "--enable") {
+                        eprintln!("Unknown detector: {}", id);
+                    }
                 }
+                None => eprintln!("{} expects a detector id", arg),
+            },
+            "--windows-root" => match args.next() {
+                Some(path) => options.windows_root = Some(PathBuf::from(path)),
+                None => eprintln!("{} expects a path", arg),
             },
-            Err(e) => eprintln!("Error querying processes: {}", e),
+            "--windows-user" => match args.next() {
+                Some(name) => options.windows_user = Some(name),
+                None => eprintln!("{} expects a user name", arg),
+            },
+            "--rescan" => options.rescan = true,
+            "--grace-period" => match args.next().as_deref().and_then(parse_grace_period) {
+                Some(grace_period) => options.grace_periods.push(grace_period),
+                None => eprintln!("{} expects [<store id or executable>=]<seconds>", arg),
+            },
+            _ => eprintln!("Unknown argument: {}", arg),
         }
-
-        thread::sleep(Duration::from_secs(1));
     }
-}
\ No newline at end of file
+
+    options
+}
+
+fn parse_grace_period(value: &str) -> Option<(Option<String>, chrono::Duration)> {
+    let (game, seconds) = match value.rsplit_once('=') {
+        Some((game, seconds)) => (Some(game.to_string()), seconds),
+        None => (None, value),
+    };
+    Some((game, chrono::Duration::seconds(seconds.parse::<u32>().ok()?.into())))
+}
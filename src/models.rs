@@ -1,18 +1,128 @@
 use chrono::{DateTime, Local};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
 #[derive(Serialize, Debug, Clone)]
 pub struct GameSession {
     pub game_name: String,
+    pub executable: String,
+    pub source: Option<String>,
+    /// The game's id in `source`, or the Steam AppID it was launched for.
+    pub store_id: Option<String>,
     pub process_id: u32,
+    pub steam_app_id: Option<u32>,
     pub start_time: DateTime<Local>,
+    pub start_time_source: StartTimeSource,
     pub last_seen: DateTime<Local>,
+    /// Time played: since `start_time`, minus the `gaps`.
     pub duration_seconds: i64,
     pub is_active: bool,
+    /// Stretches in which the game was not running, e.g. while it was
+    /// restarted after a crash.
+    pub gaps: Vec<SessionGap>,
 }
 
-#[derive(Debug, Clone)]
+/// A change in what is being played, as logged to `session_events.jsonl`.
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "event", content = "session", rename_all = "snake_case")]
+pub enum SessionEvent {
+    Started(GameSession),
+    /// The game came back within its grace period; the time it was gone is
+    /// the last of the session's `gaps`.
+    Resumed(GameSession),
+    Ended(GameSession),
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionGap {
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+}
+
+/// Where a session's `start_time` came from.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StartTimeSource {
+    /// The OS creation time of the session's first process.
+    ProcessCreation,
+    /// When the tracker first saw the session, as the OS did not say when
+    /// its processes started.
+    FirstSeen,
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct ProcessInfo {
     pub name: String,
     pub pid: u32,
+    /// Full path of the program, when the process may be inspected. For
+    /// Wine/Proton processes, the host path of the Windows executable.
+    pub exe_path: Option<PathBuf>,
+    pub argv: Vec<String>,
+    pub parent_pid: Option<u32>,
+    /// Name of the account the process runs as.
+    pub user: Option<String>,
+    pub start_time: Option<DateTime<Local>>,
+    /// Steam AppID the process was launched for, when the platform exposes it.
+    pub steam_app_id: Option<u32>,
+    /// Windows executable run by a Wine/Proton process, e.g. `witcher3.exe`.
+    pub windows_exe: Option<String>,
+}
+
+/// A process, told apart from a later one the OS gives the same pid by its
+/// creation time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ProcessId {
+    pub pid: u32,
+    pub start_time: Option<DateTime<Local>>,
+}
+
+impl ProcessId {
+    pub fn of(process: &ProcessInfo) -> Self {
+        Self { pid: process.pid, start_time: process.start_time }
+    }
+}
+
+/// Identifies a tracked session: the process tree of the process that
+/// started it, or every process Steam launched for one AppID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SessionKey {
+    Process(ProcessId),
+    SteamApp(u32),
+}
+
+/// How sure a detector is that `executables` are what the game runs.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DetectionConfidence {
+    /// Picked by heuristics from an install folder not known to hold a game.
+    Low,
+    /// A launcher knows the game, but the executable was picked by heuristics.
+    Medium,
+    /// The launcher's own data names the executable.
+    High,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DetectedGame {
+    /// The store the game belongs to, which `store_id` is scoped by. The
+    /// id of the detector that found it, e.g. `steam`, except for detectors
+    /// covering several stores: Heroic reports `heroic-epic`, `heroic-gog`
+    /// and `heroic-amazon`. Not meant for looking up detectors.
+    pub source: String,
+    /// The launcher's identifier for the game, such as a Steam AppID.
+    pub store_id: Option<String>,
+    pub title: String,
+    pub install_dir: Option<PathBuf>,
+    /// Full paths of the binaries the game runs as, best candidate first.
+    pub executables: Vec<PathBuf>,
+    pub confidence: DetectionConfidence,
+}
+
+impl DetectedGame {
+    /// Lowercase file names of `executables`, as matched against processes.
+    pub fn executable_names(&self) -> impl Iterator<Item = String> + '_ {
+        self.executables
+            .iter()
+            .filter_map(|path| path.file_name())
+            .map(|name| name.to_string_lossy().to_lowercase())
+    }
 }
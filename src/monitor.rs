@@ -0,0 +1,468 @@
+#[cfg(target_os = "linux")]
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::Duration;
+use crate::models::{ProcessId, ProcessInfo};
+use crate::system::{self, ProcessSource};
+
+/// How often processes are listed when the OS cannot notify us.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone)]
+pub enum ProcessEvent {
+    Started(ProcessInfo),
+    Exited(ProcessId),
+    /// Every running process. Sent first so processes that were already
+    /// running are known, after events were lost, and on each poll when
+    /// polling.
+    Snapshot(Vec<ProcessInfo>),
+}
+
+/// Delivers process start and exit events from a background thread.
+pub struct ProcessMonitor {
+    events: Receiver<ProcessEvent>,
+}
+
+impl ProcessMonitor {
+    /// Watches this machine's processes through OS notifications: WMI
+    /// instance events on Windows and the proc connector on Linux. Falls
+    /// back to polling when they are unavailable, e.g. because the proc
+    /// connector needs `CAP_NET_ADMIN`.
+    pub fn start() -> Self {
+        let (sender, events) = mpsc::channel();
+        if let Err(e) = start_notifications(sender.clone()) {
+            eprintln!("⚠️ Process notifications unavailable, polling instead: {}", e);
+            spawn_polling(sender, POLL_INTERVAL, system::system_process_source);
+        }
+        Self { events }
+    }
+
+    /// Lists processes from the source `open` returns every `interval`.
+    /// The source is opened on the polling thread, as WMI connections
+    /// cannot move between threads.
+    pub fn polling<F>(interval: Duration, open: F) -> Self
+    where
+        F: FnOnce() -> Result<Box<dyn ProcessSource>, Box<dyn std::error::Error>> + Send + 'static,
+    {
+        let (sender, events) = mpsc::channel();
+        spawn_polling(sender, interval, open);
+        Self { events }
+    }
+
+    /// Waits up to `timeout` for an event and returns it with every other
+    /// queued one. `None` once the monitor has stopped.
+    pub fn wait(&self, timeout: Duration) -> Option<Vec<ProcessEvent>> {
+        let first = match self.events.recv_timeout(timeout) {
+            Ok(event) => event,
+            Err(RecvTimeoutError::Timeout) => return Some(Vec::new()),
+            Err(RecvTimeoutError::Disconnected) => return None,
+        };
+
+        let mut events = vec![first];
+        events.extend(self.events.try_iter());
+        Some(events)
+    }
+}
+
+fn spawn_polling<F>(sender: Sender<ProcessEvent>, interval: Duration, open: F)
+where
+    F: FnOnce() -> Result<Box<dyn ProcessSource>, Box<dyn std::error::Error>> + Send + 'static,
+{
+    thread::spawn(move || {
+        let source = match open() {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("⚠️ Cannot list processes: {}", e);
+                return;
+            }
+        };
+
+        loop {
+            match source.processes() {
+                Ok(processes) => {
+                    if sender.send(ProcessEvent::Snapshot(processes)).is_err() {
+                        return;
+                    }
+                }
+                Err(e) => eprintln!("Error querying processes: {}", e),
+            }
+            thread::sleep(interval);
+        }
+    });
+}
+
+#[cfg(target_os = "windows")]
+fn start_notifications(sender: Sender<ProcessEvent>) -> Result<(), Box<dyn std::error::Error>> {
+    use serde::Deserialize;
+    use wmi::{COMLibrary, WMIConnection};
+    use crate::system::{process_owner, wmi_process_info, Win32_Process, WmiProcessSource};
+
+    #[allow(non_snake_case)]
+    #[derive(Deserialize)]
+    struct InstanceEvent {
+        TargetInstance: Win32_Process,
+    }
+
+    let (ready_sender, ready) = mpsc::channel();
+    // Dropped without a message unless both subscriptions succeed, which
+    // makes the threads give up theirs instead of duplicating the polling
+    // fallback's events.
+    let mut go_senders = Vec::new();
+    for event_class in ["__InstanceCreationEvent", "__InstanceDeletionEvent"] {
+        let sender = sender.clone();
+        let ready_sender = ready_sender.clone();
+        let (go_sender, go) = mpsc::channel::<()>();
+        go_senders.push(go_sender);
+
+        // Each subscription blocks its thread, and needs a connection made
+        // on that thread.
+        thread::spawn(move || {
+            let query = format!("SELECT * FROM {} WITHIN 1 WHERE TargetInstance ISA 'Win32_Process'", event_class);
+            let connection = match COMLibrary::new().and_then(|com| WMIConnection::new(com.into())) {
+                Ok(connection) => connection,
+                Err(e) => {
+                    let _ = ready_sender.send(Err(e.to_string()));
+                    return;
+                }
+            };
+            let events = match connection.raw_notification::<InstanceEvent>(&query) {
+                Ok(events) => {
+                    let _ = ready_sender.send(Ok(()));
+                    events
+                }
+                Err(e) => {
+                    let _ = ready_sender.send(Err(e.to_string()));
+                    return;
+                }
+            };
+            if go.recv().is_err() {
+                return;
+            }
+
+            for event in events {
+                let process = match event {
+                    Ok(event) => event.TargetInstance,
+                    Err(e) => {
+                        eprintln!("⚠️ Failed to read a WMI process event: {}", e);
+                        continue;
+                    }
+                };
+                let event = match event_class {
+                    "__InstanceCreationEvent" => {
+                        let user = process_owner(process.ProcessId);
+                        ProcessEvent::Started(wmi_process_info(process, user))
+                    }
+                    _ => ProcessEvent::Exited(ProcessId::of(&wmi_process_info(process, None))),
+                };
+                if sender.send(event).is_err() {
+                    return;
+                }
+            }
+        });
+    }
+
+    for _ in 0..go_senders.len() {
+        ready.recv()??;
+    }
+
+    // Subscribed before listing, so no process can slip between the two.
+    // Events from in between wait in the subscriptions until released.
+    sender.send(ProcessEvent::Snapshot(WmiProcessSource::new()?.processes()?))?;
+    for go_sender in go_senders {
+        go_sender.send(())?;
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn start_notifications(sender: Sender<ProcessEvent>) -> Result<(), Box<dyn std::error::Error>> {
+    use crate::procfs::ProcessReader;
+
+    let connector = proc_connector::ProcConnector::open()?;
+
+    thread::spawn(move || {
+        let reader = ProcessReader::new();
+        let mut start_times = HashMap::new();
+        // Subscribed before listing, so no process can slip between the two.
+        let resync = |sender: &Sender<ProcessEvent>, start_times: &mut StartTimes| match reader.list() {
+            Ok(processes) => {
+                *start_times = processes.iter().map(|p| (p.pid, p.start_time)).collect();
+                sender.send(ProcessEvent::Snapshot(processes)).is_ok()
+            }
+            Err(e) => {
+                eprintln!("Error querying processes: {}", e);
+                true
+            }
+        };
+        if !resync(&sender, &mut start_times) {
+            return;
+        }
+
+        loop {
+            let events = match connector.receive() {
+                Ok(events) => events,
+                // The kernel dropped events because we fell behind.
+                Err(e) if e.raw_os_error() == Some(libc::ENOBUFS) => {
+                    if !resync(&sender, &mut start_times) {
+                        return;
+                    }
+                    continue;
+                }
+                Err(e) => {
+                    eprintln!("⚠️ Proc connector failed, polling instead: {}", e);
+                    spawn_polling(sender, POLL_INTERVAL, system::system_process_source);
+                    return;
+                }
+            };
+
+            for event in events {
+                let Some(event) = process_event(event, &mut start_times, |pid| reader.read(pid)) else {
+                    continue;
+                };
+                if sender.send(event).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Creation times of the running processes by pid. The kernel's exit
+/// notification only names the pid, and `/proc` forgets the process before
+/// we could look.
+#[cfg(target_os = "linux")]
+type StartTimes = HashMap<u32, Option<chrono::DateTime<chrono::Local>>>;
+
+#[cfg(target_os = "linux")]
+fn process_event(
+    event: proc_connector::ConnectorEvent,
+    start_times: &mut StartTimes,
+    read: impl Fn(u32) -> Option<ProcessInfo>,
+) -> Option<ProcessEvent> {
+    match event {
+        proc_connector::ConnectorEvent::Exec(pid) => {
+            let process = read(pid)?;
+            start_times.insert(pid, process.start_time);
+            Some(ProcessEvent::Started(process))
+        }
+        proc_connector::ConnectorEvent::Exit(pid) => {
+            let start_time = start_times.remove(&pid).flatten();
+            Some(ProcessEvent::Exited(ProcessId { pid, start_time }))
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+fn start_notifications(_sender: Sender<ProcessEvent>) -> Result<(), Box<dyn std::error::Error>> {
+    Err("no process notifications on this platform".into())
+}
+
+/// The Linux kernel's process events connector, `cn_proc`.
+#[cfg(target_os = "linux")]
+mod proc_connector {
+    use std::io;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+    const CN_IDX_PROC: u32 = 1;
+    const CN_VAL_PROC: u32 = 1;
+    const PROC_CN_MCAST_LISTEN: u32 = 1;
+    const PROC_EVENT_EXEC: u32 = 0x0000_0002;
+    const PROC_EVENT_EXIT: u32 = 0x8000_0000;
+
+    const NLMSG_HEADER_LEN: usize = 16;
+    const CN_MSG_HEADER_LEN: usize = 20;
+    // `what`, `cpu` and `timestamp_ns` precede the event data.
+    const PROC_EVENT_HEADER_LEN: usize = 16;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum ConnectorEvent {
+        Exec(u32),
+        Exit(u32),
+    }
+
+    pub struct ProcConnector {
+        socket: OwnedFd,
+    }
+
+    impl ProcConnector {
+        /// Subscribes to process events. Binding to the connector's
+        /// multicast group fails without `CAP_NET_ADMIN`.
+        pub fn open() -> io::Result<Self> {
+            let fd = unsafe { libc::socket(libc::AF_NETLINK, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, libc::NETLINK_CONNECTOR) };
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let socket = unsafe { OwnedFd::from_raw_fd(fd) };
+
+            let mut address: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
+            address.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+            address.nl_pid = std::process::id();
+            address.nl_groups = CN_IDX_PROC;
+            let bound = unsafe {
+                libc::bind(
+                    socket.as_raw_fd(),
+                    (&address as *const libc::sockaddr_nl).cast(),
+                    std::mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+                )
+            };
+            if bound < 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            let message = listen_message(std::process::id());
+            let sent = unsafe { libc::send(socket.as_raw_fd(), message.as_ptr().cast(), message.len(), 0) };
+            if sent < 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            Ok(Self { socket })
+        }
+
+        /// Blocks until the kernel sends events.
+        pub fn receive(&self) -> io::Result<Vec<ConnectorEvent>> {
+            let mut buffer = [0u8; 4096];
+            loop {
+                let received = unsafe { libc::recv(self.socket.as_raw_fd(), buffer.as_mut_ptr().cast(), buffer.len(), 0) };
+                if received >= 0 {
+                    return Ok(parse_messages(&buffer[..received as usize]));
+                }
+                let error = io::Error::last_os_error();
+                if error.kind() != io::ErrorKind::Interrupted {
+                    return Err(error);
+                }
+            }
+        }
+    }
+
+    /// An `nlmsghdr` and `cn_msg` carrying `PROC_CN_MCAST_LISTEN`.
+    fn listen_message(port: u32) -> Vec<u8> {
+        let payload = PROC_CN_MCAST_LISTEN.to_ne_bytes();
+        let length = NLMSG_HEADER_LEN + CN_MSG_HEADER_LEN + payload.len();
+
+        let mut message = Vec::with_capacity(length);
+        message.extend((length as u32).to_ne_bytes());
+        message.extend((libc::NLMSG_DONE as u16).to_ne_bytes());
+        message.extend(0u16.to_ne_bytes()); // flags
+        message.extend(0u32.to_ne_bytes()); // sequence
+        message.extend(port.to_ne_bytes());
+        message.extend(CN_IDX_PROC.to_ne_bytes());
+        message.extend(CN_VAL_PROC.to_ne_bytes());
+        message.extend(0u32.to_ne_bytes()); // sequence
+        message.extend(0u32.to_ne_bytes()); // ack
+        message.extend((payload.len() as u16).to_ne_bytes());
+        message.extend(0u16.to_ne_bytes()); // flags
+        message.extend(payload);
+        message
+    }
+
+    /// Extracts exec and exit events of whole processes. Thread exits, where
+    /// the pid differs from the thread group id, are skipped.
+    pub fn parse_messages(buffer: &[u8]) -> Vec<ConnectorEvent> {
+        let u32_at = |data: &[u8], offset: usize| {
+            data.get(offset..offset + 4).map(|bytes| u32::from_ne_bytes(bytes.try_into().unwrap()))
+        };
+
+        let mut events = Vec::new();
+        let mut offset = 0;
+        while let Some(length) = u32_at(buffer, offset).map(|length| length as usize) {
+            if length < NLMSG_HEADER_LEN || offset + length > buffer.len() {
+                break;
+            }
+            let event = &buffer[offset + NLMSG_HEADER_LEN + CN_MSG_HEADER_LEN.min(length - NLMSG_HEADER_LEN)..offset + length];
+            let data = PROC_EVENT_HEADER_LEN;
+
+            if let (Some(what), Some(pid), Some(tgid)) = (u32_at(event, 0), u32_at(event, data), u32_at(event, data + 4))
+                && pid == tgid
+            {
+                match what {
+                    PROC_EVENT_EXEC => events.push(ConnectorEvent::Exec(tgid)),
+                    PROC_EVENT_EXIT => events.push(ConnectorEvent::Exit(tgid)),
+                    _ => {}
+                }
+            }
+
+            // Messages are padded to 4 bytes.
+            offset += (length + 3) & !3;
+        }
+
+        events
+    }
+
+    #[cfg(test)]
+    pub fn message(what: u32, pid: u32, tgid: u32) -> Vec<u8> {
+        let mut event = Vec::new();
+        event.extend(what.to_ne_bytes());
+        event.extend(0u32.to_ne_bytes()); // cpu
+        event.extend(0u64.to_ne_bytes()); // timestamp
+        event.extend(pid.to_ne_bytes());
+        event.extend(tgid.to_ne_bytes());
+
+        let mut message = listen_message(0);
+        message.truncate(NLMSG_HEADER_LEN + CN_MSG_HEADER_LEN);
+        message.extend(event);
+        let length = message.len() as u32;
+        message[..4].copy_from_slice(&length.to_ne_bytes());
+        message
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::ScriptedProcessSource;
+
+    fn process(name: &str, pid: u32) -> ProcessInfo {
+        ProcessInfo { name: name.to_string(), pid, ..Default::default() }
+    }
+
+    #[test]
+    fn polling_sends_snapshots() {
+        let monitor = ProcessMonitor::polling(Duration::from_millis(10), || {
+            Ok(Box::new(ScriptedProcessSource::new(vec![vec![process("hades.exe", 7)]])))
+        });
+
+        let events = monitor.wait(Duration::from_secs(5)).unwrap();
+        assert!(matches!(&events[0], ProcessEvent::Snapshot(processes) if processes[0].pid == 7));
+    }
+
+    #[test]
+    fn stops_when_processes_cannot_be_listed() {
+        let monitor = ProcessMonitor::polling(Duration::from_millis(10), || Err("access denied".into()));
+        assert!(monitor.wait(Duration::from_secs(5)).is_none());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn parses_proc_connector_messages() {
+        use proc_connector::{message, parse_messages, ConnectorEvent};
+
+        let mut buffer = message(0x0000_0002, 4242, 4242);
+        buffer.extend(message(0x8000_0000, 4243, 4242));
+        buffer.extend(message(0x8000_0000, 4242, 4242));
+        buffer.extend(message(0x0000_0001, 5000, 5000));
+
+        assert_eq!(parse_messages(&buffer), vec![ConnectorEvent::Exec(4242), ConnectorEvent::Exit(4242)]);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn reports_exits_with_the_start_time_seen_at_exec() {
+        use proc_connector::ConnectorEvent;
+
+        let started = chrono::Local::now();
+        let mut start_times = StartTimes::new();
+        let read = |pid| Some(ProcessInfo { start_time: Some(started), ..process("hades.exe", pid) });
+
+        assert!(matches!(process_event(ConnectorEvent::Exec(7), &mut start_times, read), Some(ProcessEvent::Started(_))));
+        let exited = process_event(ConnectorEvent::Exit(7), &mut start_times, read);
+        assert!(matches!(exited, Some(ProcessEvent::Exited(ProcessId { pid: 7, start_time: Some(time) })) if time == started));
+        assert!(start_times.is_empty());
+
+        // Never seen, e.g. forked without exec: only the pid is known.
+        let exited = process_event(ConnectorEvent::Exit(8), &mut start_times, read);
+        assert!(matches!(exited, Some(ProcessEvent::Exited(ProcessId { pid: 8, start_time: None }))));
+    }
+}
@@ -0,0 +1,427 @@
+use std::fmt;
+use crate::parsers::vdf::{KeyValue, KvBlock};
+
+const MAGIC_V27: u32 = 0x0756_4427;
+const MAGIC_V28: u32 = 0x0756_4428;
+const MAGIC_V29: u32 = 0x0756_4429;
+
+const TYPE_BLOCK: u8 = 0x00;
+const TYPE_STRING: u8 = 0x01;
+const TYPE_INT32: u8 = 0x02;
+const TYPE_FLOAT32: u8 = 0x03;
+const TYPE_POINTER: u8 = 0x04;
+const TYPE_WIDE_STRING: u8 = 0x05;
+const TYPE_COLOR: u8 = 0x06;
+const TYPE_UINT64: u8 = 0x07;
+const TYPE_END: u8 = 0x08;
+const TYPE_INT64: u8 = 0x0A;
+const TYPE_ALT_END: u8 = 0x0B;
+
+/// Deepest block nesting accepted. Real entries nest a handful of levels;
+/// the cap keeps a corrupt file from overflowing the stack.
+const MAX_BLOCK_DEPTH: usize = 64;
+
+/// One application record from Steam's `appcache/appinfo.vdf`.
+///
+/// Numeric values of the binary KeyValues payload are stored as their decimal
+/// string form so the tree can be queried like a text VDF document.
+#[derive(Debug, Clone)]
+pub struct AppInfo {
+    pub appid: u32,
+    pub last_updated: u32,
+    pub change_number: u32,
+    pub data: KvBlock,
+}
+
+/// A `config/launch/<n>` entry describing how Steam starts an app.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LaunchEntry {
+    pub executable: String,
+    pub arguments: String,
+    pub os_list: Option<String>,
+    pub launch_type: Option<String>,
+}
+
+impl LaunchEntry {
+    pub fn supports_os(&self, os: &str) -> bool {
+        match &self.os_list {
+            Some(list) if !list.is_empty() => list.split(',').any(|o| o.trim().eq_ignore_ascii_case(os)),
+            _ => true,
+        }
+    }
+
+    pub fn is_default(&self) -> bool {
+        match &self.launch_type {
+            Some(t) => t.is_empty() || t.eq_ignore_ascii_case("default"),
+            None => true,
+        }
+    }
+}
+
+impl AppInfo {
+    fn section(&self) -> &KvBlock {
+        self.data.get_block("appinfo").unwrap_or(&self.data)
+    }
+
+    pub fn install_dir(&self) -> Option<&str> {
+        self.section().get_block("config")?.get_str("installdir")
+    }
+
+    pub fn launch_entries(&self) -> Vec<LaunchEntry> {
+        let Some(launch) = self.section().get_block("config").and_then(|c| c.get_block("launch")) else {
+            return Vec::new();
+        };
+
+        launch
+            .iter()
+            .filter_map(|(_, value)| {
+                let entry = value.as_block()?;
+                let executable = entry.get_str("executable")?.trim();
+                if executable.is_empty() {
+                    return None;
+                }
+
+                Some(LaunchEntry {
+                    executable: executable.to_string(),
+                    arguments: entry.get_str("arguments").unwrap_or_default().to_string(),
+                    os_list: entry
+                        .get_block("config")
+                        .and_then(|c| c.get_str("oslist"))
+                        .map(|s| s.to_string()),
+                    launch_type: entry.get_str("type").map(|s| s.to_string()),
+                })
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppInfoError {
+    pub offset: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AppInfoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "appinfo.vdf parse error at offset {:#x}: {}", self.offset, self.message)
+    }
+}
+
+impl std::error::Error for AppInfoError {}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn error(&self, message: impl Into<String>) -> AppInfoError {
+        AppInfoError { offset: self.pos, message: message.into() }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], AppInfoError> {
+        let end = self.pos.checked_add(len).filter(|&end| end <= self.data.len())
+            .ok_or_else(|| self.error("unexpected end of file"))?;
+        let slice = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, AppInfoError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, AppInfoError> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, AppInfoError> {
+        Ok(u64::from_le_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+
+    fn cstring(&mut self) -> Result<String, AppInfoError> {
+        let rest = &self.data[self.pos..];
+        let len = rest.iter().position(|&b| b == 0)
+            .ok_or_else(|| self.error("unterminated string"))?;
+        let value = String::from_utf8_lossy(&rest[..len]).into_owned();
+        self.pos += len + 1;
+        Ok(value)
+    }
+
+    fn wide_cstring(&mut self) -> Result<String, AppInfoError> {
+        let mut units = Vec::new();
+        loop {
+            let bytes = self.bytes(2)?;
+            let unit = u16::from_le_bytes([bytes[0], bytes[1]]);
+            if unit == 0 {
+                break;
+            }
+            units.push(unit);
+        }
+        Ok(String::from_utf16_lossy(&units))
+    }
+}
+
+/// Parses the whole `appinfo.vdf` file.
+///
+/// Versions 27 and 28 store key names inline; version 29 stores them in a
+/// string table at the end of the file and refers to them by index.
+pub fn parse(data: &[u8]) -> Result<Vec<AppInfo>, AppInfoError> {
+    let mut reader = Reader { data, pos: 0 };
+
+    let magic = reader.u32()?;
+    if !matches!(magic, MAGIC_V27 | MAGIC_V28 | MAGIC_V29) {
+        return Err(AppInfoError { offset: 0, message: format!("unknown magic {:#010x}", magic) });
+    }
+    let _universe = reader.u32()?;
+
+    let string_table = if magic == MAGIC_V29 {
+        let offset = reader.u64()? as usize;
+        Some(read_string_table(data, offset)?)
+    } else {
+        None
+    };
+
+    let mut apps = Vec::new();
+    loop {
+        let appid = reader.u32()?;
+        if appid == 0 {
+            break;
+        }
+
+        let size = reader.u32()? as usize;
+        let entry_end = reader.pos.checked_add(size).filter(|&end| end <= data.len())
+            .ok_or_else(|| reader.error(format!("entry for app {} runs past end of file", appid)))?;
+
+        let _info_state = reader.u32()?;
+        let last_updated = reader.u32()?;
+        let _pics_token = reader.u64()?;
+        let _text_sha1 = reader.bytes(20)?;
+        let change_number = reader.u32()?;
+        if magic != MAGIC_V27 {
+            let _binary_sha1 = reader.bytes(20)?;
+        }
+
+        let data = read_block(&mut reader, string_table.as_deref(), 0)?;
+        reader.pos = entry_end;
+
+        apps.push(AppInfo { appid, last_updated, change_number, data });
+    }
+
+    Ok(apps)
+}
+
+fn read_string_table(data: &[u8], offset: usize) -> Result<Vec<String>, AppInfoError> {
+    if offset > data.len() {
+        return Err(AppInfoError { offset, message: "string table offset past end of file".into() });
+    }
+
+    let mut reader = Reader { data, pos: offset };
+    let count = reader.u32()? as usize;
+    let mut strings = Vec::with_capacity(count.min(data.len()));
+    for _ in 0..count {
+        strings.push(reader.cstring()?);
+    }
+    Ok(strings)
+}
+
+fn read_key(reader: &mut Reader, string_table: Option<&[String]>) -> Result<String, AppInfoError> {
+    match string_table {
+        Some(table) => {
+            let index = reader.u32()? as usize;
+            table.get(index).cloned()
+                .ok_or_else(|| reader.error(format!("string table index {} out of range", index)))
+        }
+        None => reader.cstring(),
+    }
+}
+
+fn read_block(reader: &mut Reader, string_table: Option<&[String]>, depth: usize) -> Result<KvBlock, AppInfoError> {
+    if depth > MAX_BLOCK_DEPTH {
+        return Err(reader.error(format!("blocks nested deeper than {}", MAX_BLOCK_DEPTH)));
+    }
+    let mut block = KvBlock::default();
+
+    loop {
+        let value_type = reader.u8()?;
+        if value_type == TYPE_END || value_type == TYPE_ALT_END {
+            return Ok(block);
+        }
+
+        let key = read_key(reader, string_table)?;
+        let value = match value_type {
+            TYPE_BLOCK => KeyValue::Block(read_block(reader, string_table, depth + 1)?),
+            TYPE_STRING => KeyValue::String(reader.cstring()?),
+            TYPE_WIDE_STRING => KeyValue::String(reader.wide_cstring()?),
+            TYPE_INT32 | TYPE_POINTER | TYPE_COLOR => {
+                KeyValue::String((reader.u32()? as i32).to_string())
+            }
+            TYPE_FLOAT32 => KeyValue::String(f32::from_bits(reader.u32()?).to_string()),
+            TYPE_UINT64 => KeyValue::String(reader.u64()?.to_string()),
+            TYPE_INT64 => KeyValue::String((reader.u64()? as i64).to_string()),
+            other => return Err(reader.error(format!("unknown value type {:#04x}", other))),
+        };
+
+        block.push(key, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes a binary KeyValues payload; keys go inline or into `table`.
+    struct Writer {
+        buf: Vec<u8>,
+        table: Option<Vec<String>>,
+    }
+
+    impl Writer {
+        fn key(&mut self, value_type: u8, key: &str) {
+            self.buf.push(value_type);
+            match &mut self.table {
+                Some(table) => {
+                    let index = table.iter().position(|k| k == key).unwrap_or_else(|| {
+                        table.push(key.to_string());
+                        table.len() - 1
+                    });
+                    self.buf.extend((index as u32).to_le_bytes());
+                }
+                None => {
+                    self.buf.extend(key.as_bytes());
+                    self.buf.push(0);
+                }
+            }
+        }
+
+        fn string(&mut self, key: &str, value: &str) {
+            self.key(TYPE_STRING, key);
+            self.buf.extend(value.as_bytes());
+            self.buf.push(0);
+        }
+
+        fn int(&mut self, key: &str, value: i32) {
+            self.key(TYPE_INT32, key);
+            self.buf.extend(value.to_le_bytes());
+        }
+
+        fn open(&mut self, key: &str) {
+            self.key(TYPE_BLOCK, key);
+        }
+
+        fn close(&mut self) {
+            self.buf.push(TYPE_END);
+        }
+    }
+
+    fn build(magic: u32) -> Vec<u8> {
+        let mut kv = Writer {
+            buf: Vec::new(),
+            table: (magic == MAGIC_V29).then(Vec::new),
+        };
+        kv.open("appinfo");
+        kv.int("appid", 1245620);
+        kv.open("config");
+        kv.string("installdir", "ELDEN RING");
+        kv.open("launch");
+        kv.open("0");
+        kv.string("executable", r"Game\eldenring.exe");
+        kv.string("type", "default");
+        kv.open("config");
+        kv.string("oslist", "windows");
+        kv.close();
+        kv.close();
+        kv.open("1");
+        kv.string("executable", "start_protected_game.exe");
+        kv.string("arguments", "-eac");
+        kv.close();
+        kv.close();
+        kv.close();
+        kv.close();
+        kv.close();
+
+        let mut entry = Vec::new();
+        entry.extend(2u32.to_le_bytes());
+        entry.extend(1_700_000_000u32.to_le_bytes());
+        entry.extend(0u64.to_le_bytes());
+        entry.extend([0u8; 20]);
+        entry.extend(42u32.to_le_bytes());
+        if magic != MAGIC_V27 {
+            entry.extend([0u8; 20]);
+        }
+        entry.extend(&kv.buf);
+
+        let mut file = Vec::new();
+        file.extend(magic.to_le_bytes());
+        file.extend(1u32.to_le_bytes());
+        let table_offset_pos = file.len();
+        if magic == MAGIC_V29 {
+            file.extend(0u64.to_le_bytes());
+        }
+        file.extend(1245620u32.to_le_bytes());
+        file.extend((entry.len() as u32).to_le_bytes());
+        file.extend(entry);
+        file.extend(0u32.to_le_bytes());
+
+        if let Some(table) = kv.table {
+            let offset = file.len() as u64;
+            file[table_offset_pos..table_offset_pos + 8].copy_from_slice(&offset.to_le_bytes());
+            file.extend((table.len() as u32).to_le_bytes());
+            for key in table {
+                file.extend(key.as_bytes());
+                file.push(0);
+            }
+        }
+        file
+    }
+
+    #[test]
+    fn parses_every_supported_version() {
+        for magic in [MAGIC_V27, MAGIC_V28, MAGIC_V29] {
+            let apps = parse(&build(magic)).unwrap();
+            assert_eq!(apps.len(), 1, "magic {:#x}", magic);
+
+            let app = &apps[0];
+            assert_eq!(app.appid, 1245620);
+            assert_eq!(app.change_number, 42);
+            assert_eq!(app.install_dir(), Some("ELDEN RING"));
+            assert_eq!(app.data.get_block("appinfo").unwrap().get_u64("appid"), Some(1245620));
+
+            let launch = app.launch_entries();
+            assert_eq!(launch.len(), 2);
+            assert_eq!(launch[0].executable, r"Game\eldenring.exe");
+            assert!(launch[0].is_default() && launch[0].supports_os("windows"));
+            assert!(!launch[0].supports_os("linux"));
+            assert_eq!(launch[1].arguments, "-eac");
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_magic_and_truncated_files() {
+        assert!(parse(&[0x26, 0x44, 0x56, 0x07, 1, 0, 0, 0]).is_err());
+
+        let mut data = build(MAGIC_V28);
+        data.truncate(data.len() - 10);
+        assert!(parse(&data).is_err());
+    }
+
+    #[test]
+    fn rejects_deeply_nested_blocks() {
+        let mut kv = Writer { buf: Vec::new(), table: None };
+        for _ in 0..=MAX_BLOCK_DEPTH {
+            kv.open("a");
+        }
+        for _ in 0..=MAX_BLOCK_DEPTH {
+            kv.close();
+        }
+        kv.close();
+        let mut reader = Reader { data: &kv.buf, pos: 0 };
+        let error = read_block(&mut reader, None, 0).unwrap_err();
+        assert_eq!(error.message, format!("blocks nested deeper than {}", MAX_BLOCK_DEPTH));
+
+        // One level shallower is fine.
+        let nested = &kv.buf[3..kv.buf.len() - 1];
+        let mut reader = Reader { data: nested, pos: 0 };
+        assert!(read_block(&mut reader, None, 0).is_ok());
+    }
+}
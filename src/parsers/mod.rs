@@ -0,0 +1,4 @@
+pub mod appinfo;
+pub mod pe;
+pub mod protobuf;
+pub mod vdf;
@@ -0,0 +1,405 @@
+use std::fmt;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Headers are read in one go; the section table of real binaries ends well
+/// before this.
+const HEADER_READ_SIZE: usize = 4096;
+
+/// Resource sections bigger than this are not worth reading for a few strings.
+const MAX_RESOURCE_SECTION: u32 = 32 * 1024 * 1024;
+
+const PE32_MAGIC: u16 = 0x10b;
+const PE32_PLUS_MAGIC: u16 = 0x20b;
+
+const RESOURCE_DIRECTORY: usize = 2;
+const CLR_RUNTIME_DIRECTORY: usize = 14;
+const RT_VERSION: u32 = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Machine {
+    I386,
+    Amd64,
+    Arm64,
+    Other(u16),
+}
+
+impl Machine {
+    fn from_raw(raw: u16) -> Self {
+        match raw {
+            0x14c => Machine::I386,
+            0x8664 => Machine::Amd64,
+            0xaa64 => Machine::Arm64,
+            other => Machine::Other(other),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Subsystem {
+    WindowsGui,
+    WindowsConsole,
+    Other(u16),
+}
+
+impl Subsystem {
+    fn from_raw(raw: u16) -> Self {
+        match raw {
+            2 => Subsystem::WindowsGui,
+            3 => Subsystem::WindowsConsole,
+            other => Subsystem::Other(other),
+        }
+    }
+}
+
+/// Strings from the `StringFileInfo` block of a `VS_VERSIONINFO` resource.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VersionInfo {
+    pub product_name: Option<String>,
+    pub company_name: Option<String>,
+    pub file_description: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeInfo {
+    pub machine: Machine,
+    pub subsystem: Subsystem,
+    /// Managed assemblies carry a CLR runtime header.
+    pub is_dotnet: bool,
+    pub version: Option<VersionInfo>,
+}
+
+impl PeInfo {
+    pub fn is_64_bit(&self) -> bool {
+        matches!(self.machine, Machine::Amd64 | Machine::Arm64)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeError {
+    pub offset: usize,
+    pub message: String,
+}
+
+impl fmt::Display for PeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PE parse error at offset {}: {}", self.offset, self.message)
+    }
+}
+
+impl std::error::Error for PeError {}
+
+#[derive(Debug, Clone, Copy)]
+struct Section {
+    virtual_address: u32,
+    virtual_size: u32,
+    raw_size: u32,
+    raw_offset: u32,
+}
+
+impl Section {
+    fn contains(&self, rva: u32) -> bool {
+        let size = self.virtual_size.max(self.raw_size);
+        rva >= self.virtual_address && rva - self.virtual_address < size
+    }
+}
+
+struct Headers {
+    machine: Machine,
+    subsystem: Subsystem,
+    data_directories: Vec<(u32, u32)>,
+    sections: Vec<Section>,
+}
+
+impl Headers {
+    fn directory(&self, index: usize) -> Option<(u32, u32)> {
+        self.data_directories.get(index).copied().filter(|&(rva, size)| rva != 0 && size != 0)
+    }
+
+    fn resource_section(&self) -> Option<(Section, u32)> {
+        let (rva, _) = self.directory(RESOURCE_DIRECTORY)?;
+        let section = self.sections.iter().find(|s| s.contains(rva))?;
+        Some((*section, rva))
+    }
+
+    fn into_info(self, resource_data: Option<&[u8]>) -> PeInfo {
+        let version = match (resource_data, self.resource_section()) {
+            (Some(data), Some((section, rva))) => read_version_resource(data, &section, rva),
+            _ => None,
+        };
+
+        PeInfo {
+            machine: self.machine,
+            subsystem: self.subsystem,
+            is_dotnet: self.directory(CLR_RUNTIME_DIRECTORY).is_some(),
+            version,
+        }
+    }
+}
+
+/// Parses a PE image held entirely in memory.
+pub fn parse(data: &[u8]) -> Result<PeInfo, PeError> {
+    let headers = parse_headers(data)?;
+    let resource_data = headers.resource_section().and_then(|(section, _)| {
+        let start = section.raw_offset as usize;
+        data.get(start..start.checked_add(section.raw_size as usize)?)
+    });
+    Ok(headers.into_info(resource_data))
+}
+
+/// Reads only the headers and the resource section, so large game binaries
+/// are cheap to inspect.
+pub fn read_file(path: &Path) -> Result<PeInfo, Box<dyn std::error::Error>> {
+    let mut file = std::fs::File::open(path)?;
+
+    let mut header = Vec::with_capacity(HEADER_READ_SIZE);
+    file.by_ref().take(HEADER_READ_SIZE as u64).read_to_end(&mut header)?;
+    let headers = parse_headers(&header)?;
+
+    let resource_data = match headers.resource_section() {
+        Some((section, _)) if section.raw_size <= MAX_RESOURCE_SECTION => {
+            let mut data = vec![0u8; section.raw_size as usize];
+            file.seek(SeekFrom::Start(u64::from(section.raw_offset)))?;
+            file.read_exact(&mut data).ok().map(|_| data)
+        }
+        _ => None,
+    };
+
+    Ok(headers.into_info(resource_data.as_deref()))
+}
+
+fn parse_headers(data: &[u8]) -> Result<Headers, PeError> {
+    if data.get(..2) != Some(&b"MZ"[..]) {
+        return Err(PeError { offset: 0, message: "missing MZ signature".into() });
+    }
+
+    let pe_offset = read_u32(data, 0x3c)? as usize;
+    if data.get(pe_offset..pe_offset + 4) != Some(&b"PE\0\0"[..]) {
+        return Err(PeError { offset: pe_offset, message: "missing PE signature".into() });
+    }
+
+    let coff = pe_offset + 4;
+    let machine = Machine::from_raw(read_u16(data, coff)?);
+    let section_count = read_u16(data, coff + 2)? as usize;
+    let optional_size = read_u16(data, coff + 16)? as usize;
+
+    let optional = coff + 20;
+    let directories_at = match read_u16(data, optional)? {
+        PE32_MAGIC => optional + 96,
+        PE32_PLUS_MAGIC => optional + 112,
+        magic => {
+            return Err(PeError { offset: optional, message: format!("unknown optional header magic {:#x}", magic) });
+        }
+    };
+    let subsystem = Subsystem::from_raw(read_u16(data, optional + 68)?);
+
+    let directory_count = read_u32(data, directories_at - 4)?.min(16) as usize;
+    let data_directories = (0..directory_count)
+        .map(|i| Ok((read_u32(data, directories_at + i * 8)?, read_u32(data, directories_at + i * 8 + 4)?)))
+        .collect::<Result<Vec<_>, PeError>>()?;
+
+    let section_table = optional + optional_size;
+    let sections = (0..section_count)
+        .map(|i| {
+            let at = section_table + i * 40;
+            Ok(Section {
+                virtual_size: read_u32(data, at + 8)?,
+                virtual_address: read_u32(data, at + 12)?,
+                raw_size: read_u32(data, at + 16)?,
+                raw_offset: read_u32(data, at + 20)?,
+            })
+        })
+        .collect::<Result<Vec<_>, PeError>>()?;
+
+    Ok(Headers { machine, subsystem, data_directories, sections })
+}
+
+/// Walks the resource tree (type -> name -> language) to the first
+/// `RT_VERSION` entry. `data` is the raw resource section.
+fn read_version_resource(data: &[u8], section: &Section, resource_rva: u32) -> Option<VersionInfo> {
+    let root = (resource_rva - section.virtual_address) as usize;
+
+    let names = find_subdirectory(data, root, root, Some(RT_VERSION))?;
+    let languages = find_subdirectory(data, root, names, None)?;
+    let entry = first_entry(data, languages)?;
+    let (offset, is_directory) = entry_target(data, entry)?;
+    if is_directory {
+        return None;
+    }
+
+    let data_entry = root + offset;
+    let data_rva = read_u32(data, data_entry).ok()?;
+    let size = read_u32(data, data_entry + 4).ok()? as usize;
+    let start = data_rva.checked_sub(section.virtual_address)? as usize;
+    parse_version_info(data.get(start..start.checked_add(size)?)?)
+}
+
+fn first_entry(data: &[u8], directory: usize) -> Option<usize> {
+    let count = read_u16(data, directory + 12).ok()? as usize + read_u16(data, directory + 14).ok()? as usize;
+    (count > 0).then_some(directory + 16)
+}
+
+/// Returns the offset of the first subdirectory, or of the one with the
+/// given integer id.
+fn find_subdirectory(data: &[u8], root: usize, directory: usize, id: Option<u32>) -> Option<usize> {
+    let count = read_u16(data, directory + 12).ok()? as usize + read_u16(data, directory + 14).ok()? as usize;
+
+    (0..count).find_map(|i| {
+        let entry = directory + 16 + i * 8;
+        let name = read_u32(data, entry).ok()?;
+        if id.is_some_and(|id| id != name) {
+            return None;
+        }
+        match entry_target(data, entry)? {
+            (offset, true) => Some(root + offset),
+            _ => None,
+        }
+    })
+}
+
+fn entry_target(data: &[u8], entry: usize) -> Option<(usize, bool)> {
+    let target = read_u32(data, entry + 4).ok()?;
+    Some(((target & 0x7fff_ffff) as usize, target & 0x8000_0000 != 0))
+}
+
+/// One node of the `VS_VERSIONINFO` tree: every block starts with its
+/// length, value length, type and a NUL-terminated UTF-16 key.
+struct VersionBlock<'a> {
+    key: String,
+    value: &'a [u8],
+    is_text: bool,
+    children: &'a [u8],
+}
+
+fn parse_version_block(data: &[u8]) -> Option<(VersionBlock<'_>, usize)> {
+    let length = read_u16(data, 0).ok()? as usize;
+    let value_length = read_u16(data, 2).ok()? as usize;
+    let is_text = read_u16(data, 4).ok()? == 1;
+    let block = data.get(..length)?;
+
+    let mut key = Vec::new();
+    let mut pos = 6;
+    loop {
+        let unit = read_u16(block, pos).ok()?;
+        pos += 2;
+        if unit == 0 {
+            break;
+        }
+        key.push(unit);
+    }
+    pos = align4(pos);
+
+    // Text values count UTF-16 units, binary ones count bytes.
+    let value_bytes = if is_text { value_length * 2 } else { value_length };
+    let value = block.get(pos..(pos + value_bytes).min(length))?;
+    let children = block.get(align4(pos + value_bytes).min(length)..)?;
+
+    let block = VersionBlock { key: String::from_utf16_lossy(&key), value, is_text, children };
+    Some((block, align4(length)))
+}
+
+fn version_children(data: &[u8]) -> impl Iterator<Item = VersionBlock<'_>> {
+    let mut rest = data;
+    std::iter::from_fn(move || {
+        let (block, length) = parse_version_block(rest)?;
+        rest = rest.get(length..).unwrap_or_default();
+        Some(block)
+    })
+}
+
+fn parse_version_info(data: &[u8]) -> Option<VersionInfo> {
+    let (root, _) = parse_version_block(data)?;
+    if root.key != "VS_VERSION_INFO" {
+        return None;
+    }
+
+    let mut info = VersionInfo::default();
+    let strings = version_children(root.children)
+        .filter(|block| block.key == "StringFileInfo")
+        .flat_map(|file_info| version_children(file_info.children))
+        .flat_map(|table| version_children(table.children));
+
+    for string in strings {
+        let value = decode_utf16(string.value, string.is_text);
+        if value.is_empty() {
+            continue;
+        }
+        let field = match string.key.as_str() {
+            "ProductName" => &mut info.product_name,
+            "CompanyName" => &mut info.company_name,
+            "FileDescription" => &mut info.file_description,
+            _ => continue,
+        };
+        // The first string table is the binary's primary language.
+        field.get_or_insert(value);
+    }
+
+    Some(info)
+}
+
+fn decode_utf16(bytes: &[u8], is_text: bool) -> String {
+    if !is_text {
+        return String::new();
+    }
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .take_while(|&unit| unit != 0)
+        .collect();
+    String::from_utf16_lossy(&units).trim().to_string()
+}
+
+fn align4(pos: usize) -> usize {
+    (pos + 3) & !3
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, PeError> {
+    data.get(offset..offset + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or_else(|| PeError { offset, message: "unexpected end of data".into() })
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, PeError> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| PeError { offset, message: "unexpected end of data".into() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> std::path::PathBuf {
+        std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join("pe").join(name)
+    }
+
+    #[test]
+    fn reads_gui_binary_with_version_strings() {
+        let info = read_file(&fixture("game.exe")).unwrap();
+        assert_eq!(info.machine, Machine::Amd64);
+        assert_eq!(info.subsystem, Subsystem::WindowsGui);
+        assert!(info.is_64_bit());
+        assert!(!info.is_dotnet);
+
+        let version = info.version.unwrap();
+        assert_eq!(version.product_name.as_deref(), Some("Hollow Knight"));
+        assert_eq!(version.company_name.as_deref(), Some("Team Cherry"));
+        assert_eq!(version.file_description.as_deref(), Some("Hollow Knight"));
+    }
+
+    #[test]
+    fn reads_console_dotnet_binary() {
+        let data = std::fs::read(fixture("tool32.exe")).unwrap();
+        let info = parse(&data).unwrap();
+        assert_eq!(info.machine, Machine::I386);
+        assert_eq!(info.subsystem, Subsystem::WindowsConsole);
+        assert!(info.is_dotnet);
+        assert_eq!(info.version, None);
+    }
+
+    #[test]
+    fn rejects_non_pe_data() {
+        assert!(parse(b"\x7fELF\x02\x01\x01").is_err());
+        assert!(parse(b"MZ").is_err());
+    }
+}
@@ -0,0 +1,167 @@
+use std::fmt;
+
+/// A single protobuf field as it appears on the wire. Messages are decoded
+/// without a schema; callers pick fields by number and interpret them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WireValue<'a> {
+    Varint(u64),
+    Fixed64(u64),
+    Bytes(&'a [u8]),
+    Fixed32(u32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Field<'a> {
+    pub number: u32,
+    pub value: WireValue<'a>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProtobufError {
+    pub offset: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ProtobufError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "protobuf decode error at offset {}: {}", self.offset, self.message)
+    }
+}
+
+impl std::error::Error for ProtobufError {}
+
+/// A decoded message: its fields in wire order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Message<'a> {
+    fields: Vec<Field<'a>>,
+}
+
+impl<'a> Message<'a> {
+    pub fn decode(data: &'a [u8]) -> Result<Self, ProtobufError> {
+        let mut fields = Vec::new();
+        let mut pos = 0;
+
+        while pos < data.len() {
+            let key = read_varint(data, &mut pos)?;
+            let number = (key >> 3) as u32;
+            if number == 0 {
+                return Err(ProtobufError { offset: pos, message: "field number 0".into() });
+            }
+
+            let value = match key & 0x7 {
+                0 => WireValue::Varint(read_varint(data, &mut pos)?),
+                1 => WireValue::Fixed64(u64::from_le_bytes(take(data, &mut pos, 8)?.try_into().unwrap())),
+                2 => {
+                    let len = read_varint(data, &mut pos)? as usize;
+                    WireValue::Bytes(take(data, &mut pos, len)?)
+                }
+                5 => WireValue::Fixed32(u32::from_le_bytes(take(data, &mut pos, 4)?.try_into().unwrap())),
+                wire_type => {
+                    return Err(ProtobufError {
+                        offset: pos,
+                        message: format!("unsupported wire type {}", wire_type),
+                    });
+                }
+            };
+
+            fields.push(Field { number, value });
+        }
+
+        Ok(Self { fields })
+    }
+
+    fn all(&self, number: u32) -> impl Iterator<Item = &WireValue<'a>> {
+        self.fields.iter().filter(move |f| f.number == number).map(|f| &f.value)
+    }
+
+    /// Last occurrence wins, as in the protobuf spec.
+    fn last(&self, number: u32) -> Option<&WireValue<'a>> {
+        self.all(number).last()
+    }
+
+    pub fn string(&self, number: u32) -> Option<String> {
+        match self.last(number)? {
+            WireValue::Bytes(bytes) => Some(String::from_utf8_lossy(bytes).into_owned()),
+            _ => None,
+        }
+    }
+
+    pub fn varint(&self, number: u32) -> Option<u64> {
+        match self.last(number)? {
+            WireValue::Varint(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn bool(&self, number: u32) -> Option<bool> {
+        self.varint(number).map(|v| v != 0)
+    }
+
+    pub fn message(&self, number: u32) -> Option<Message<'a>> {
+        match self.last(number)? {
+            WireValue::Bytes(bytes) => Message::decode(bytes).ok(),
+            _ => None,
+        }
+    }
+
+    /// Decodes every occurrence of a repeated message field, skipping
+    /// entries that are not valid messages.
+    pub fn messages(&self, number: u32) -> Vec<Message<'a>> {
+        self.all(number)
+            .filter_map(|value| match value {
+                WireValue::Bytes(bytes) => Message::decode(bytes).ok(),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+fn read_varint(data: &[u8], pos: &mut usize) -> Result<u64, ProtobufError> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *data.get(*pos).ok_or_else(|| ProtobufError {
+            offset: *pos,
+            message: "truncated varint".into(),
+        })?;
+        *pos += 1;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(ProtobufError { offset: *pos, message: "varint longer than 10 bytes".into() })
+}
+
+fn take<'a>(data: &'a [u8], pos: &mut usize, len: usize) -> Result<&'a [u8], ProtobufError> {
+    let end = pos.checked_add(len).filter(|&end| end <= data.len()).ok_or_else(|| ProtobufError {
+        offset: *pos,
+        message: format!("field of {} bytes runs past end of input", len),
+    })?;
+    let slice = &data[*pos..end];
+    *pos = end;
+    Ok(slice)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_scalars_and_nested_messages() {
+        // field 1: varint 150, field 2: "hi", field 3: { field 1: true }, field 4: fixed32
+        let data = [0x08, 0x96, 0x01, 0x12, 0x02, b'h', b'i', 0x1a, 0x02, 0x08, 0x01, 0x25, 1, 0, 0, 0];
+        let message = Message::decode(&data).unwrap();
+
+        assert_eq!(message.varint(1), Some(150));
+        assert_eq!(message.string(2).as_deref(), Some("hi"));
+        assert_eq!(message.message(3).unwrap().bool(1), Some(true));
+        assert_eq!(message.varint(4), None);
+        assert_eq!(message.messages(3).len(), 1);
+    }
+
+    #[test]
+    fn rejects_truncated_input() {
+        assert!(Message::decode(&[0x12, 0x05, b'a']).is_err());
+        assert!(Message::decode(&[0x08, 0x80]).is_err());
+    }
+}
@@ -0,0 +1,293 @@
+use std::fmt;
+
+/// A parsed text KeyValues (VDF) node: either a plain string or a nested block.
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyValue {
+    String(String),
+    Block(KvBlock),
+}
+
+impl KeyValue {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            KeyValue::String(s) => Some(s),
+            KeyValue::Block(_) => None,
+        }
+    }
+
+    pub fn as_block(&self) -> Option<&KvBlock> {
+        match self {
+            KeyValue::Block(b) => Some(b),
+            KeyValue::String(_) => None,
+        }
+    }
+}
+
+/// An ordered list of key/value pairs. Keys are compared case-insensitively,
+/// the way Steam itself treats them.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct KvBlock {
+    entries: Vec<(String, KeyValue)>,
+}
+
+impl KvBlock {
+    pub fn get(&self, key: &str) -> Option<&KeyValue> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v)
+    }
+
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.get(key)?.as_str()
+    }
+
+    pub fn get_block(&self, key: &str) -> Option<&KvBlock> {
+        self.get(key)?.as_block()
+    }
+
+    pub fn get_u64(&self, key: &str) -> Option<u64> {
+        self.get_str(key)?.trim().parse().ok()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &KeyValue)> {
+        self.entries.iter().map(|(k, v)| (k.as_str(), v))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn push(&mut self, key: impl Into<String>, value: KeyValue) {
+        self.entries.push((key.into(), value));
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VdfError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for VdfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "VDF parse error on line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for VdfError {}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Str(String),
+    Open,
+    Close,
+}
+
+struct Lexer<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    line: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(input: &'a str) -> Self {
+        let input = input.strip_prefix('\u{feff}').unwrap_or(input);
+        Self { chars: input.chars().peekable(), line: 1 }
+    }
+
+    fn error(&self, message: impl Into<String>) -> VdfError {
+        VdfError { line: self.line, message: message.into() }
+    }
+
+    fn skip_whitespace_and_comments(&mut self) {
+        while let Some(&c) = self.chars.peek() {
+            if c == '\n' {
+                self.line += 1;
+                self.chars.next();
+            } else if c.is_whitespace() {
+                self.chars.next();
+            } else if c == '/' {
+                let mut lookahead = self.chars.clone();
+                lookahead.next();
+                if lookahead.peek() != Some(&'/') {
+                    return;
+                }
+                while let Some(&c) = self.chars.peek() {
+                    if c == '\n' {
+                        break;
+                    }
+                    self.chars.next();
+                }
+            } else {
+                return;
+            }
+        }
+    }
+
+    fn next_token(&mut self) -> Result<Option<Token>, VdfError> {
+        loop {
+            self.skip_whitespace_and_comments();
+
+            let Some(c) = self.chars.next() else {
+                return Ok(None);
+            };
+
+            return match c {
+                '{' => Ok(Some(Token::Open)),
+                '}' => Ok(Some(Token::Close)),
+                '"' => self.quoted().map(|s| Some(Token::Str(s))),
+                // Platform conditionals such as [$WIN32] are ignored.
+                '[' => {
+                    for c in self.chars.by_ref() {
+                        if c == ']' {
+                            break;
+                        }
+                    }
+                    continue;
+                }
+                c => Ok(Some(Token::Str(self.unquoted(c)))),
+            };
+        }
+    }
+
+    fn quoted(&mut self) -> Result<String, VdfError> {
+        let mut value = String::new();
+        while let Some(c) = self.chars.next() {
+            match c {
+                '"' => return Ok(value),
+                '\\' => match self.chars.next() {
+                    Some('n') => value.push('\n'),
+                    Some('t') => value.push('\t'),
+                    Some('\\') => value.push('\\'),
+                    Some('"') => value.push('"'),
+                    Some(other) => {
+                        value.push('\\');
+                        value.push(other);
+                    }
+                    None => break,
+                },
+                '\n' => {
+                    self.line += 1;
+                    value.push(c);
+                }
+                _ => value.push(c),
+            }
+        }
+        Err(self.error("unterminated quoted string"))
+    }
+
+    fn unquoted(&mut self, first: char) -> String {
+        let mut value = String::from(first);
+        while let Some(&c) = self.chars.peek() {
+            if c.is_whitespace() || matches!(c, '"' | '{' | '}' | '[') {
+                break;
+            }
+            value.push(c);
+            self.chars.next();
+        }
+        value
+    }
+}
+
+/// Deepest block nesting accepted. Real files nest a handful of levels;
+/// the cap keeps a corrupt file from overflowing the stack.
+const MAX_BLOCK_DEPTH: usize = 64;
+
+/// Parses a text KeyValues document into its top-level block.
+///
+/// A file such as `libraryfolders.vdf` yields a block with a single
+/// `libraryfolders` entry holding everything else.
+pub fn parse(input: &str) -> Result<KvBlock, VdfError> {
+    let mut lexer = Lexer::new(input);
+    parse_block(&mut lexer, 0)
+}
+
+fn parse_block(lexer: &mut Lexer, depth: usize) -> Result<KvBlock, VdfError> {
+    if depth > MAX_BLOCK_DEPTH {
+        return Err(lexer.error(format!("blocks nested deeper than {}", MAX_BLOCK_DEPTH)));
+    }
+    let nested = depth > 0;
+    let mut block = KvBlock::default();
+
+    loop {
+        let key = match lexer.next_token()? {
+            Some(Token::Str(key)) => key,
+            Some(Token::Close) if nested => return Ok(block),
+            Some(Token::Close) => return Err(lexer.error("unexpected '}'")),
+            Some(Token::Open) => return Err(lexer.error("expected a key, found '{'")),
+            None if nested => return Err(lexer.error("unexpected end of input, missing '}'")),
+            None => return Ok(block),
+        };
+
+        let value = match lexer.next_token()? {
+            Some(Token::Str(value)) => KeyValue::String(value),
+            Some(Token::Open) => KeyValue::Block(parse_block(lexer, depth + 1)?),
+            Some(Token::Close) | None => {
+                return Err(lexer.error(format!("missing value for key \"{}\"", key)));
+            }
+        };
+
+        block.push(key, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIBRARY_FOLDERS: &str = include_str!("../../tests/fixtures/steam/libraryfolders.vdf");
+    const APP_MANIFEST: &str = include_str!("../../tests/fixtures/steam/appmanifest_292030.acf");
+
+    #[test]
+    fn parses_library_folders() {
+        let root = parse(LIBRARY_FOLDERS).unwrap();
+        let folders = root.get_block("libraryfolders").unwrap();
+
+        let first = folders.get_block("0").unwrap();
+        assert_eq!(first.get_str("path"), Some(r"C:\Program Files (x86)\Steam"));
+        let apps: Vec<&str> = first.get_block("apps").unwrap().iter().map(|(k, _)| k).collect();
+        assert_eq!(apps, vec!["228980", "292030"]);
+
+        let second = folders.get_block("1").unwrap();
+        assert_eq!(second.get_str("path"), Some(r#"D:\Games\Steam "Library""#));
+    }
+
+    #[test]
+    fn parses_app_manifest_case_insensitively() {
+        let root = parse(APP_MANIFEST).unwrap();
+        let state = root.get_block("appstate").unwrap();
+
+        assert_eq!(state.get_u64("AppID"), Some(292030));
+        assert_eq!(state.get_str("name"), Some("The Witcher 3: Wild Hunt"));
+        assert_eq!(state.get_str("InstallDir"), Some("The Witcher 3"));
+        assert_eq!(state.get_u64("StateFlags"), Some(4));
+        assert_eq!(state.get_u64("LastUpdated"), Some(1700000000));
+    }
+
+    #[test]
+    fn handles_split_lines_comments_and_unquoted_tokens() {
+        let input = "// comment\nroot\n{\n\t\"key\"\n\t\"value\" [$WIN32]\n\tbare token\n\tnested { inner \"1\" }\n}\n";
+        let root = parse(input).unwrap();
+        let block = root.get_block("ROOT").unwrap();
+
+        assert_eq!(block.get_str("key"), Some("value"));
+        assert_eq!(block.get_str("bare"), Some("token"));
+        assert_eq!(block.get_block("nested").unwrap().get_str("inner"), Some("1"));
+    }
+
+    #[test]
+    fn reports_unbalanced_braces() {
+        let err = parse("\"root\"\n{\n\"key\" \"value\"\n").unwrap_err();
+        assert_eq!(err.line, 4);
+        assert!(parse("}").is_err());
+    }
+
+    #[test]
+    fn rejects_deeply_nested_blocks() {
+        let nested = |depth: usize| format!("{}{}", "a {".repeat(depth), "}".repeat(depth));
+
+        let err = parse(&nested(MAX_BLOCK_DEPTH + 1)).unwrap_err();
+        assert_eq!(err.message, format!("blocks nested deeper than {}", MAX_BLOCK_DEPTH));
+        assert!(parse(&nested(MAX_BLOCK_DEPTH)).is_ok());
+    }
+}
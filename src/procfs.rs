@@ -0,0 +1,363 @@
+use chrono::{DateTime, Local};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::detectors::join_relative;
+use crate::models::ProcessInfo;
+
+// Environment variables Steam sets for everything it launches, in order of
+// preference. SteamGameId also carries 64-bit ids for non-Steam shortcuts,
+// which do not fit in a u32 and are skipped.
+const STEAM_APP_ID_VARS: &[&str] = &["SteamAppId", "SteamGameId", "STEAM_COMPAT_APP_ID"];
+
+// `comm` keeps at most TASK_COMM_LEN - 1 bytes of the program name.
+const COMM_MAX_LEN: usize = 15;
+
+/// Lists running processes from `/proc`, tagging each with the Steam AppID it
+/// was launched for and, for Wine/Proton processes, the Windows executable it
+/// is running.
+pub fn list_processes() -> std::io::Result<Vec<ProcessInfo>> {
+    ProcessReader::new().list()
+}
+
+/// Reads processes from `/proc`, looking up the boot time and user names
+/// once rather than for every process.
+pub struct ProcessReader {
+    host: Host,
+}
+
+impl Default for ProcessReader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProcessReader {
+    pub fn new() -> Self {
+        Self { host: Host::read() }
+    }
+
+    pub fn list(&self) -> std::io::Result<Vec<ProcessInfo>> {
+        let mut processes = Vec::new();
+
+        for entry in fs::read_dir("/proc")?.flatten() {
+            let Some(pid) = entry.file_name().to_str().and_then(|s| s.parse::<u32>().ok()) else {
+                continue;
+            };
+
+            // Processes can exit between listing and reading; skip them quietly.
+            if let Some(process) = read_process(&entry.path(), pid, &self.host) {
+                processes.push(process);
+            }
+        }
+
+        Ok(processes)
+    }
+
+    /// Reads one process, or `None` if it already exited.
+    pub fn read(&self, pid: u32) -> Option<ProcessInfo> {
+        read_process(&Path::new("/proc").join(pid.to_string()), pid, &self.host)
+    }
+}
+
+/// What every process is read against.
+struct Host {
+    /// Seconds since the Unix epoch at which the machine booted.
+    boot_time: Option<u64>,
+    /// `USER_HZ`, the unit of process times in `/proc`. `None` if unknown,
+    /// which leaves start times unknown too.
+    ticks_per_second: Option<u64>,
+    user_names: HashMap<u32, String>,
+}
+
+impl Host {
+    fn read() -> Self {
+        let boot_time = fs::read_to_string("/proc/stat").ok().and_then(|stat| parse_boot_time(&stat));
+        let user_names = fs::read_to_string("/etc/passwd").map(|passwd| parse_passwd(&passwd)).unwrap_or_default();
+        // SAFETY: sysconf only reads a system setting and takes no pointers.
+        let ticks_per_second = match unsafe { libc::sysconf(libc::_SC_CLK_TCK) } {
+            hz if hz > 0 => Some(hz as u64),
+            _ => {
+                eprintln!("⚠️ Cannot read USER_HZ, process start times will be unknown: {}", std::io::Error::last_os_error());
+                None
+            }
+        };
+        Self { boot_time, ticks_per_second, user_names }
+    }
+
+    /// Converts a start time in clock ticks since boot to wall-clock time.
+    fn start_time(&self, start_ticks: u64) -> Option<DateTime<Local>> {
+        let ticks_per_second = self.ticks_per_second?;
+        let secs = self.boot_time? + start_ticks / ticks_per_second;
+        let nanos = (start_ticks % ticks_per_second) * 1_000_000_000 / ticks_per_second;
+        DateTime::from_timestamp(i64::try_from(secs).ok()?, nanos as u32).map(|utc| utc.with_timezone(&Local))
+    }
+
+    fn user_name(&self, uid: u32) -> String {
+        self.user_names.get(&uid).cloned().unwrap_or_else(|| uid.to_string())
+    }
+}
+
+fn read_process(proc_dir: &Path, pid: u32, host: &Host) -> Option<ProcessInfo> {
+    let comm = fs::read_to_string(proc_dir.join("comm")).ok()?.trim_end().to_string();
+    let argv = fs::read(proc_dir.join("cmdline")).map(|b| parse_nul_separated(&b)).unwrap_or_default();
+    // environ is only readable for our own processes, which is where games run.
+    let environ = fs::read(proc_dir.join("environ")).map(|b| parse_environ(&b)).unwrap_or_default();
+    let stat = fs::read_to_string(proc_dir.join("stat")).ok().and_then(|stat| parse_stat(&stat));
+    let uid = fs::read_to_string(proc_dir.join("status")).ok().and_then(|status| parse_uid(&status));
+
+    let windows_exe = windows_exe_arg(&argv);
+    let exe_path = match windows_exe {
+        Some(windows_path) => wine_host_path(windows_path, &environ),
+        None => fs::read_link(proc_dir.join("exe")).ok(),
+    };
+
+    Some(ProcessInfo {
+        name: full_name(comm, exe_path.as_deref(), &argv),
+        pid,
+        steam_app_id: steam_app_id(&environ, &argv),
+        windows_exe: windows_exe_name(&argv),
+        exe_path,
+        parent_pid: stat.as_ref().map(|stat| stat.parent_pid).filter(|&ppid| ppid != 0),
+        user: uid.map(|uid| host.user_name(uid)),
+        start_time: stat.and_then(|stat| host.start_time(stat.start_ticks)),
+        argv,
+    })
+}
+
+struct Stat {
+    parent_pid: u32,
+    start_ticks: u64,
+}
+
+/// Reads the parent pid and start time from `/proc/<pid>/stat`. The
+/// program name in it may hold spaces and parentheses, so fields are
+/// counted from the last `)`.
+fn parse_stat(stat: &str) -> Option<Stat> {
+    let fields: Vec<&str> = stat[stat.rfind(')')? + 1..].split_whitespace().collect();
+    Some(Stat {
+        parent_pid: fields.get(1)?.parse().ok()?,
+        start_ticks: fields.get(19)?.parse().ok()?,
+    })
+}
+
+/// The real user id from `/proc/<pid>/status`.
+fn parse_uid(status: &str) -> Option<u32> {
+    let line = status.lines().find_map(|line| line.strip_prefix("Uid:"))?;
+    line.split_whitespace().next()?.parse().ok()
+}
+
+fn parse_boot_time(stat: &str) -> Option<u64> {
+    stat.lines().find_map(|line| line.strip_prefix("btime "))?.trim().parse().ok()
+}
+
+fn parse_passwd(passwd: &str) -> HashMap<u32, String> {
+    passwd
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let uid = fields.nth(1)?.parse().ok()?;
+            Some((uid, name.to_string()))
+        })
+        .collect()
+}
+
+/// `comm` is cut off at 15 bytes. A longer name is recovered from the
+/// program path or argv[0], whichever it is the start of.
+fn full_name(comm: String, exe_path: Option<&Path>, argv: &[String]) -> String {
+    if comm.len() < COMM_MAX_LEN {
+        return comm;
+    }
+
+    let exe_name = exe_path.and_then(|path| path.file_name()).map(|name| name.to_string_lossy());
+    let arg_name = argv.first().and_then(|arg| arg.rsplit(['\\', '/']).next()).map(|name| name.into());
+    exe_name
+        .into_iter()
+        .chain(arg_name)
+        .find(|name| name.len() > comm.len() && name.starts_with(comm.as_str()))
+        .map(|name| name.into_owned())
+        .unwrap_or(comm)
+}
+
+/// Maps a Windows path such as `C:\Games\Game.exe` to the host through the
+/// `dosdevices` links of the Wine prefix the process runs in.
+fn wine_host_path(windows_path: &str, environ: &HashMap<String, String>) -> Option<PathBuf> {
+    let prefix = match (environ.get("WINEPREFIX"), environ.get("STEAM_COMPAT_DATA_PATH"), environ.get("HOME")) {
+        (Some(prefix), _, _) => PathBuf::from(prefix),
+        (None, Some(compat_data), _) => Path::new(compat_data).join("pfx"),
+        (None, None, Some(home)) => Path::new(home).join(".wine"),
+        (None, None, None) => return None,
+    };
+
+    let (drive, rest) = windows_path.split_once(':')?;
+    if drive.len() != 1 {
+        return None;
+    }
+    let root = fs::canonicalize(prefix.join("dosdevices").join(format!("{}:", drive.to_ascii_lowercase()))).ok()?;
+    Some(join_relative(&root, rest))
+}
+
+fn parse_nul_separated(bytes: &[u8]) -> Vec<String> {
+    bytes
+        .split(|&b| b == 0)
+        .filter(|part| !part.is_empty())
+        .map(|part| String::from_utf8_lossy(part).into_owned())
+        .collect()
+}
+
+fn parse_environ(bytes: &[u8]) -> HashMap<String, String> {
+    parse_nul_separated(bytes)
+        .into_iter()
+        .filter_map(|pair| {
+            let (key, value) = pair.split_once('=')?;
+            Some((key.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// Finds the Steam AppID from the environment or from a
+/// `reaper SteamLaunch AppId=NNN -- ...` command line.
+fn steam_app_id(environ: &HashMap<String, String>, argv: &[String]) -> Option<u32> {
+    let from_env = STEAM_APP_ID_VARS
+        .iter()
+        .filter_map(|var| environ.get(*var))
+        .find_map(|value| parse_app_id(value));
+
+    from_env.or_else(|| {
+        argv.iter()
+            .take_while(|arg| arg.as_str() != "--")
+            .find_map(|arg| {
+                let (key, value) = arg.split_once('=')?;
+                if key.eq_ignore_ascii_case("AppId") { parse_app_id(value) } else { None }
+            })
+    })
+}
+
+fn parse_app_id(value: &str) -> Option<u32> {
+    value.trim().parse::<u32>().ok().filter(|&id| id != 0)
+}
+
+/// Loaders that start a Windows program given as their first argument.
+const WINE_LOADERS: &[&str] = &["wine", "wine64", "wine-preloader", "wine64-preloader"];
+
+/// Wine rewrites argv[0] to the Windows path of the program, e.g.
+/// `Z:\home\user\Games\witcher3.exe`, and the preloader may pass it as
+/// the next argument. Other hosts of `.exe` files, like `mono Foo.exe`,
+/// are not Wine and are left alone.
+fn windows_exe_arg(argv: &[String]) -> Option<&str> {
+    let is_exe = |arg: &&String| arg.to_lowercase().ends_with(".exe");
+    let program = argv.first()?;
+    if is_windows_path(program) {
+        return Some(program).filter(is_exe).map(|arg| arg.as_str());
+    }
+
+    let loader = program.rsplit('/').next().unwrap_or(program);
+    if !WINE_LOADERS.contains(&loader) {
+        return None;
+    }
+    argv.get(1).filter(is_exe).map(|arg| arg.as_str())
+}
+
+/// `C:\...` style paths, which no Linux program is started as.
+fn is_windows_path(arg: &str) -> bool {
+    let bytes = arg.as_bytes();
+    bytes.len() > 3 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' && bytes[2] == b'\\'
+}
+
+fn windows_exe_name(argv: &[String]) -> Option<String> {
+    windows_exe_arg(argv)
+        .and_then(|arg| arg.rsplit(['\\', '/']).next())
+        .filter(|name| !name.is_empty())
+        .map(|name| name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn reads_app_id_from_environment() {
+        let environ = parse_environ(b"HOME=/home/deck\0SteamGameId=292030\0SteamAppId=292030\0");
+        assert_eq!(steam_app_id(&environ, &[]), Some(292030));
+
+        let shortcut = parse_environ(b"SteamGameId=12988761390124548096\0");
+        assert_eq!(steam_app_id(&shortcut, &[]), None);
+    }
+
+    #[test]
+    fn reads_app_id_from_reaper_command_line() {
+        let argv = parse_nul_separated(
+            b"/home/deck/.steam/steam/ubuntu12_32/reaper\0SteamLaunch\0AppId=1245620\0--\0/proton\0waitforexitandrun\0",
+        );
+        assert_eq!(steam_app_id(&HashMap::new(), &argv), Some(1245620));
+    }
+
+    #[test]
+    fn extracts_windows_exe_from_wine_argv() {
+        let argv = args(&[r"Z:\home\deck\.steam\steam\steamapps\common\The Witcher 3\bin\x64\witcher3.exe"]);
+        assert_eq!(windows_exe_name(&argv), Some("witcher3.exe".to_string()));
+
+        let preloader = args(&["/usr/bin/wine64-preloader", r"C:\Games\Game.EXE", "-dx12"]);
+        assert_eq!(windows_exe_name(&preloader), Some("Game.EXE".to_string()));
+
+        assert_eq!(windows_exe_name(&args(&["/usr/bin/bash", "-c", "run.exe"])), None);
+        assert_eq!(windows_exe_name(&args(&["mono", "Foo.exe"])), None);
+        assert_eq!(windows_exe_name(&args(&["/usr/bin/dotnet", "x.exe"])), None);
+        assert_eq!(windows_exe_name(&args(&["/opt/tools/Foo.exe"])), None);
+    }
+
+    #[test]
+    fn reads_parent_start_time_and_owner() {
+        let stat = parse_stat("4242 (Game (Main) 1) S 4200 4242 4200 0 -1 4194560 9 0 0 0 3 1 0 0 20 0 12 0 12345 0 0").unwrap();
+        assert_eq!((stat.parent_pid, stat.start_ticks), (4200, 12345));
+
+        let host = Host {
+            boot_time: parse_boot_time("cpu  1 2 3\nbtime 1700000000\nprocesses 9\n"),
+            ticks_per_second: Some(100),
+            user_names: parse_passwd("root:x:0:0:root:/root:/bin/bash\ndeck:x:1000:1000::/home/deck:/bin/bash\n"),
+        };
+        let start = host.start_time(stat.start_ticks).unwrap();
+        assert_eq!(start.timestamp(), 1_700_000_123);
+        assert_eq!(start.timestamp_subsec_millis(), 450);
+
+        assert_eq!(parse_uid("Name:\tgame\nUid:\t1000\t1000\t1000\t1000\n"), Some(1000));
+        assert_eq!(host.user_name(1000), "deck");
+        assert_eq!(host.user_name(1001), "1001");
+
+        let fast_clock = Host { ticks_per_second: Some(1000), ..host };
+        assert_eq!(fast_clock.start_time(12345).unwrap().timestamp_subsec_millis(), 345);
+        assert!(Host { ticks_per_second: None, ..fast_clock }.start_time(12345).is_none());
+    }
+
+    #[test]
+    fn recovers_names_cut_off_by_comm() {
+        let exe = Path::new("/opt/game/SuperLongGameName-Linux");
+        assert_eq!(full_name("SuperLongGameNa".to_string(), Some(exe), &[]), "SuperLongGameName-Linux");
+        assert_eq!(
+            full_name("Cyberpunk2077.e".to_string(), None, &args(&[r"C:\Games\Cyberpunk2077.exe"])),
+            "Cyberpunk2077.exe"
+        );
+        assert_eq!(full_name("bash".to_string(), Some(Path::new("/usr/bin/bash")), &[]), "bash");
+    }
+
+    #[test]
+    fn maps_wine_paths_through_the_prefix() {
+        let prefix = std::env::temp_dir().join(format!("procfs_prefix_{}", std::process::id()));
+        std::fs::create_dir_all(prefix.join("drive_c").join("Games")).unwrap();
+        std::fs::create_dir_all(prefix.join("dosdevices")).unwrap();
+        std::os::unix::fs::symlink("../drive_c", prefix.join("dosdevices").join("c:")).unwrap();
+
+        let environ = HashMap::from([("WINEPREFIX".to_string(), prefix.display().to_string())]);
+        let path = wine_host_path(r"C:\Games\Hades\Hades.exe", &environ);
+        let drive_c = std::fs::canonicalize(prefix.join("drive_c")).unwrap();
+        std::fs::remove_dir_all(&prefix).unwrap();
+
+        assert_eq!(path, Some(drive_c.join("Games").join("Hades").join("Hades.exe")));
+        assert_eq!(wine_host_path(r"D:\Hades.exe", &environ), None);
+    }
+}
@@ -0,0 +1,428 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use crate::registry::memory::typed_value;
+use crate::registry::{RegValue, RegistrySource, RootKey};
+
+/// Cell offsets in a hive are relative to the first hive bin, which follows
+/// the 4 KiB base block.
+const HBIN_START: usize = 0x1000;
+
+/// Key and value names stored as Latin-1 rather than UTF-16.
+const KEY_COMP_NAME: u16 = 0x20;
+const VALUE_COMP_NAME: u16 = 0x1;
+
+/// Values bigger than this are split into `db` segments (hive format 1.4+).
+const BIG_DATA_THRESHOLD: usize = 16344;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HiveError {
+    pub offset: usize,
+    pub message: String,
+}
+
+impl fmt::Display for HiveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "registry hive error at offset {:#x}: {}", self.offset, self.message)
+    }
+}
+
+impl std::error::Error for HiveError {}
+
+/// A registry hive file (`regf`) such as `SOFTWARE` or `NTUSER.DAT`, read
+/// as-is. Pending changes in `.LOG` files are not replayed, so a hive from
+/// a Windows install that was not shut down cleanly may be slightly stale.
+pub struct Hive {
+    data: Vec<u8>,
+    root: usize,
+}
+
+impl Hive {
+    pub fn open(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self::parse(std::fs::read(path)?)?)
+    }
+
+    pub fn parse(data: Vec<u8>) -> Result<Self, HiveError> {
+        if data.get(..4) != Some(&b"regf"[..]) {
+            return Err(HiveError { offset: 0, message: "missing regf signature".into() });
+        }
+        let root = read_u32(&data, 36)? as usize;
+        let hive = Self { data, root };
+        hive.key_node(root)?;
+        Ok(hive)
+    }
+
+    /// Returns the cell data at a hive offset, without its size field.
+    fn cell(&self, offset: usize) -> Result<&[u8], HiveError> {
+        let start = HBIN_START + offset;
+        let size = read_u32(&self.data, start)? as i32;
+        // Allocated cells have a negative size.
+        let len = size.unsigned_abs() as usize;
+        if len < 4 {
+            return Err(HiveError { offset: start, message: format!("bad cell size {}", size) });
+        }
+        self.data
+            .get(start + 4..start + len)
+            .ok_or_else(|| HiveError { offset: start, message: "cell runs past end of hive".into() })
+    }
+
+    fn key_node(&self, offset: usize) -> Result<KeyNode<'_>, HiveError> {
+        let cell = self.cell(offset)?;
+        if cell.get(..2) != Some(&b"nk"[..]) {
+            return Err(HiveError { offset: HBIN_START + offset, message: "expected a key node".into() });
+        }
+        Ok(KeyNode { cell })
+    }
+
+    /// Collects the key node offsets of a subkey list, following `ri` index
+    /// roots. An `ri` index may only point at `lf`, `lh` or `li` lists, so a
+    /// corrupt hive whose index points back at itself cannot recurse forever.
+    fn subkey_offsets(&self, list_offset: usize, out: &mut Vec<usize>) -> Result<(), HiveError> {
+        let list = self.cell(list_offset)?;
+        if list.get(..2) != Some(&b"ri"[..]) {
+            return self.leaf_subkey_offsets(list_offset, out);
+        }
+
+        let count = read_u16(list, 2)? as usize;
+        for i in 0..count {
+            self.leaf_subkey_offsets(read_u32(list, 4 + i * 4)? as usize, out)?;
+        }
+        Ok(())
+    }
+
+    fn leaf_subkey_offsets(&self, list_offset: usize, out: &mut Vec<usize>) -> Result<(), HiveError> {
+        let list = self.cell(list_offset)?;
+        let count = read_u16(list, 2)? as usize;
+
+        match list.get(..2) {
+            Some(b"lf") | Some(b"lh") => {
+                for i in 0..count {
+                    out.push(read_u32(list, 4 + i * 8)? as usize);
+                }
+            }
+            Some(b"li") => {
+                for i in 0..count {
+                    out.push(read_u32(list, 4 + i * 4)? as usize);
+                }
+            }
+            Some(b"ri") => {
+                return Err(HiveError { offset: HBIN_START + list_offset, message: "nested subkey index".into() });
+            }
+            _ => {
+                return Err(HiveError { offset: HBIN_START + list_offset, message: "unknown subkey list".into() });
+            }
+        }
+        Ok(())
+    }
+
+    fn subkeys(&self, key: &KeyNode) -> Vec<KeyNode<'_>> {
+        let mut offsets = Vec::new();
+        if key.subkey_count() > 0 && self.subkey_offsets(key.subkey_list() as usize, &mut offsets).is_err() {
+            return Vec::new();
+        }
+        offsets.into_iter().filter_map(|offset| self.key_node(offset).ok()).collect()
+    }
+
+    fn find_key(&self, path: &str) -> Option<KeyNode<'_>> {
+        let mut key = self.key_node(self.root).ok()?;
+        for part in path.split('\\').filter(|p| !p.is_empty()) {
+            key = self.subkeys(&key).into_iter().find(|k| k.name().eq_ignore_ascii_case(part))?;
+        }
+        Some(key)
+    }
+
+    fn values(&self, key: &KeyNode) -> Vec<ValueNode<'_>> {
+        let count = key.value_count();
+        if count == 0 {
+            return Vec::new();
+        }
+        let Ok(list) = self.cell(key.value_list() as usize) else {
+            return Vec::new();
+        };
+
+        // A corrupt count must not send us through billions of misses.
+        (0..count.min(list.len() / 4))
+            .map_while(|i| read_u32(list, i * 4).ok())
+            .filter_map(|offset| self.cell(offset as usize).ok())
+            .filter(|cell| cell.get(..2) == Some(&b"vk"[..]))
+            .map(|cell| ValueNode { cell })
+            .collect()
+    }
+
+    fn value_data(&self, value: &ValueNode) -> Option<Vec<u8>> {
+        let raw_size = read_u32(value.cell, 4).ok()?;
+        let offset = read_u32(value.cell, 8).ok()?;
+
+        // Up to four bytes are stored in the offset field itself.
+        if raw_size & 0x8000_0000 != 0 {
+            let size = (raw_size & 0x7fff_ffff) as usize;
+            return Some(offset.to_le_bytes()[..size.min(4)].to_vec());
+        }
+
+        let size = raw_size as usize;
+        let cell = self.cell(offset as usize).ok()?;
+        if size > BIG_DATA_THRESHOLD && cell.get(..2) == Some(&b"db"[..]) {
+            let segments = read_u16(cell, 2).ok()? as usize;
+            let list = self.cell(read_u32(cell, 4).ok()? as usize).ok()?;
+            let mut data = Vec::with_capacity(size);
+            for i in 0..segments {
+                let segment = self.cell(read_u32(list, i * 4).ok()? as usize).ok()?;
+                let take = (size - data.len()).min(segment.len()).min(BIG_DATA_THRESHOLD);
+                data.extend_from_slice(&segment[..take]);
+            }
+            return Some(data);
+        }
+
+        cell.get(..size).map(|data| data.to_vec())
+    }
+}
+
+struct KeyNode<'a> {
+    cell: &'a [u8],
+}
+
+impl KeyNode<'_> {
+    fn field(&self, offset: usize) -> u32 {
+        read_u32(self.cell, offset).unwrap_or(0)
+    }
+
+    fn subkey_count(&self) -> u32 {
+        self.field(20)
+    }
+
+    fn subkey_list(&self) -> u32 {
+        self.field(28)
+    }
+
+    fn value_count(&self) -> usize {
+        self.field(36) as usize
+    }
+
+    fn value_list(&self) -> u32 {
+        self.field(40)
+    }
+
+    fn name(&self) -> String {
+        let flags = read_u16(self.cell, 2).unwrap_or(0);
+        let len = read_u16(self.cell, 72).unwrap_or(0) as usize;
+        decode_name(self.cell.get(76..76 + len).unwrap_or_default(), flags & KEY_COMP_NAME != 0)
+    }
+}
+
+struct ValueNode<'a> {
+    cell: &'a [u8],
+}
+
+impl ValueNode<'_> {
+    /// The default value of a key has an empty name.
+    fn name(&self) -> String {
+        let len = read_u16(self.cell, 2).unwrap_or(0) as usize;
+        let flags = read_u16(self.cell, 16).unwrap_or(0);
+        decode_name(self.cell.get(20..20 + len).unwrap_or_default(), flags & VALUE_COMP_NAME != 0)
+    }
+
+    fn data_type(&self) -> u32 {
+        read_u32(self.cell, 12).unwrap_or(0)
+    }
+}
+
+fn decode_name(bytes: &[u8], latin1: bool) -> String {
+    if latin1 {
+        bytes.iter().map(|&b| b as char).collect()
+    } else {
+        let units: Vec<u16> = bytes.chunks_exact(2).map(|pair| u16::from_le_bytes([pair[0], pair[1]])).collect();
+        String::from_utf16_lossy(&units)
+    }
+}
+
+/// Hives mounted at the registry paths they back, e.g. `SOFTWARE` at
+/// `HKLM\SOFTWARE` and a user's `NTUSER.DAT` at `HKCU`.
+#[derive(Default)]
+pub struct HiveRegistry {
+    mounts: Vec<(RootKey, String, Hive)>,
+    /// Hive files loaded by [`HiveRegistry::from_windows_install`].
+    files: Vec<PathBuf>,
+}
+
+impl HiveRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn mount(&mut self, root: RootKey, path: &str, hive: Hive) {
+        self.mounts.push((root, path.trim_matches('\\').to_string(), hive));
+    }
+
+    /// Loads the machine `SOFTWARE` hive and, if given, one user's
+    /// `NTUSER.DAT` from a mounted Windows partition.
+    pub fn from_windows_install(windows_root: &Path, user: Option<&str>) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut registry = Self::new();
+
+        let software = find_path(windows_root, &["Windows", "System32", "config", "SOFTWARE"])
+            .ok_or("no Windows/System32/config/SOFTWARE hive found")?;
+        registry.mount(RootKey::LocalMachine, "SOFTWARE", Hive::open(&software)?);
+        registry.files.push(software);
+
+        if let Some(user) = user {
+            let ntuser = find_path(windows_root, &["Users", user, "NTUSER.DAT"])
+                .ok_or_else(|| format!("no NTUSER.DAT found for user {}", user))?;
+            registry.mount(RootKey::CurrentUser, "", Hive::open(&ntuser)?);
+            registry.files.push(ntuser);
+        }
+
+        Ok(registry)
+    }
+
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    /// Finds the hive backing `path` and the path relative to its root.
+    fn resolve(&self, root: RootKey, path: &str) -> Option<(&Hive, String)> {
+        let path = path.trim_matches('\\');
+        self.mounts.iter().find_map(|(mount_root, mount_path, hive)| {
+            if *mount_root != root {
+                return None;
+            }
+            if mount_path.is_empty() {
+                return Some((hive, path.to_string()));
+            }
+            let prefix = path.get(..mount_path.len())?;
+            let rest = &path[mount_path.len()..];
+            if !prefix.eq_ignore_ascii_case(mount_path) || !(rest.is_empty() || rest.starts_with('\\')) {
+                return None;
+            }
+            Some((hive, rest.to_string()))
+        })
+    }
+}
+
+impl RegistrySource for HiveRegistry {
+    fn subkeys(&self, root: RootKey, path: &str) -> Vec<String> {
+        let Some((hive, path)) = self.resolve(root, path) else {
+            return Vec::new();
+        };
+        hive.find_key(&path)
+            .map(|key| hive.subkeys(&key).iter().map(|k| k.name()).collect())
+            .unwrap_or_default()
+    }
+
+    fn value(&self, root: RootKey, path: &str, name: &str) -> Option<RegValue> {
+        let (hive, path) = self.resolve(root, path)?;
+        let key = hive.find_key(&path)?;
+        let value = hive.values(&key).into_iter().find(|v| v.name().eq_ignore_ascii_case(name))?;
+        let data = hive.value_data(&value)?;
+        Some(typed_value(value.data_type(), data))
+    }
+}
+
+/// Joins `parts` onto `base`, matching each component case-insensitively
+/// since NTFS mounts may be case-sensitive on Linux.
+fn find_path(base: &Path, parts: &[&str]) -> Option<PathBuf> {
+    parts.iter().try_fold(base.to_path_buf(), |dir, part| {
+        let exact = dir.join(part);
+        if exact.exists() {
+            return Some(exact);
+        }
+        std::fs::read_dir(&dir)
+            .ok()?
+            .flatten()
+            .find(|entry| entry.file_name().to_string_lossy().eq_ignore_ascii_case(part))
+            .map(|entry| entry.path())
+    })
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, HiveError> {
+    data.get(offset..offset + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or_else(|| HiveError { offset, message: "unexpected end of data".into() })
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, HiveError> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| HiveError { offset, message: "unexpected end of data".into() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture_dir() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join("registry")
+    }
+
+    fn software_registry() -> HiveRegistry {
+        let mut registry = HiveRegistry::new();
+        registry.mount(RootKey::LocalMachine, "SOFTWARE", Hive::open(&fixture_dir().join("SOFTWARE")).unwrap());
+        registry
+    }
+
+    #[test]
+    fn walks_all_subkey_list_kinds() {
+        let registry = software_registry();
+
+        // `lh` list under Uninstall, `ri` -> `li` under Installs, `lf` elsewhere.
+        let mut uninstall = registry.subkeys(RootKey::LocalMachine, r"SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall");
+        uninstall.sort();
+        assert_eq!(uninstall, vec!["Hollow Knight_is1", "{7-Zip}"]);
+
+        let mut installs = registry.subkeys(RootKey::LocalMachine, r"software\wow6432node\ubisoft\launcher\installs");
+        installs.sort();
+        assert_eq!(installs, vec!["4311", "5266"]);
+
+        assert!(registry.subkeys(RootKey::LocalMachine, r"SYSTEM\Select").is_empty());
+        assert!(registry.subkeys(RootKey::CurrentUser, "SOFTWARE").is_empty());
+    }
+
+    #[test]
+    fn reads_typed_values() {
+        let registry = software_registry();
+        let key = r"SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall\Hollow Knight_is1";
+
+        assert_eq!(registry.string(RootKey::LocalMachine, key, "DisplayName"), Some("Hollow Knight".to_string()));
+        assert_eq!(registry.value(RootKey::LocalMachine, key, "EstimatedSize"), Some(RegValue::Dword(9_437_184)));
+        assert_eq!(registry.string(RootKey::LocalMachine, key, ""), Some("default".to_string()));
+        assert_eq!(
+            registry.string(RootKey::LocalMachine, r"SOFTWARE\WOW6432Node\Ubisoft\Launcher\Installs\5266", "InstallDir"),
+            Some(r"D:\Games\Far Cry 6\".to_string())
+        );
+
+        let notes = registry.value(RootKey::LocalMachine, key, "Notes").unwrap();
+        assert!(matches!(notes, RegValue::Binary(bytes) if bytes.len() == 20_000 && bytes[19_999] == 0xab));
+    }
+
+    #[test]
+    fn rejects_subkey_indexes_that_point_at_indexes() {
+        let mut hive = Hive::open(&fixture_dir().join("SOFTWARE")).unwrap();
+        let list = hive.find_key(r"WOW6432Node\Ubisoft\Launcher\Installs").unwrap().subkey_list() as usize;
+        assert_eq!(hive.cell(list).unwrap().get(..2), Some(&b"ri"[..]));
+
+        // Point the index's first entry back at the index itself.
+        let entry = HBIN_START + list + 4 + 4;
+        hive.data[entry..entry + 4].copy_from_slice(&(list as u32).to_le_bytes());
+
+        let error = hive.subkey_offsets(list, &mut Vec::new()).unwrap_err();
+        assert_eq!(error.message, "nested subkey index");
+        let key = hive.find_key(r"WOW6432Node\Ubisoft\Launcher").unwrap();
+        assert!(hive.find_key(r"WOW6432Node\Ubisoft\Launcher\Installs\4311").is_none());
+        assert_eq!(hive.subkeys(&key).len(), 1);
+    }
+
+    #[test]
+    fn bounds_value_counts_by_the_value_list() {
+        let mut hive = Hive::open(&fixture_dir().join("SOFTWARE")).unwrap();
+        let path = r"WOW6432Node\Ubisoft\Launcher\Installs\5266";
+        // Where the key's value count field is in the file.
+        let count = hive.find_key(path).unwrap().cell.as_ptr() as usize - hive.data.as_ptr() as usize + 36;
+        hive.data[count..count + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        let key = hive.find_key(path).unwrap();
+        assert_eq!(key.value_count(), u32::MAX as usize);
+        assert_eq!(hive.values(&key).len(), 1);
+    }
+
+    #[test]
+    fn rejects_non_hive_files() {
+        assert!(Hive::parse(b"REGEDIT4\r\n".to_vec()).is_err());
+    }
+}
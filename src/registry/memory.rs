@@ -0,0 +1,366 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::Path;
+use crate::registry::{RegValue, RegistrySource, RootKey};
+
+/// A registry held in memory, typically loaded from a `.reg` export.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryRegistry {
+    /// Keyed by hive and lowercase path.
+    keys: BTreeMap<(RootKey, String), MemoryKey>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct MemoryKey {
+    /// The last path component as originally written.
+    name: String,
+    /// Keyed by lowercase value name; the default value is `""`.
+    values: HashMap<String, RegValue>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegFileError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for RegFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, ".reg parse error on line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for RegFileError {}
+
+impl MemoryRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a key and any missing parents.
+    pub fn create_key(&mut self, root: RootKey, path: &str) {
+        let mut current = String::new();
+        for part in path.split('\\').filter(|p| !p.is_empty()) {
+            if !current.is_empty() {
+                current.push('\\');
+            }
+            current.push_str(&part.to_lowercase());
+            self.keys
+                .entry((root, current.clone()))
+                .or_insert_with(|| MemoryKey { name: part.to_string(), values: HashMap::new() });
+        }
+    }
+
+    pub fn set_value(&mut self, root: RootKey, path: &str, name: &str, value: RegValue) {
+        self.create_key(root, path);
+        if let Some(key) = self.keys.get_mut(&(root, normalize_path(path))) {
+            key.values.insert(name.to_lowercase(), value);
+        }
+    }
+
+    /// Removes a key together with its subkeys.
+    pub fn delete_key(&mut self, root: RootKey, path: &str) {
+        let path = normalize_path(path);
+        let prefix = format!(r"{}\", path);
+        self.keys.retain(|(r, p), _| *r != root || (*p != path && !p.starts_with(&prefix)));
+    }
+
+    fn delete_value(&mut self, root: RootKey, path: &str, name: &str) {
+        if let Some(key) = self.keys.get_mut(&(root, normalize_path(path))) {
+            key.values.remove(&name.to_lowercase());
+        }
+    }
+
+    /// Reads a `.reg` file as written by `regedit` (UTF-16) or `REGEDIT4` (UTF-8).
+    pub fn load_reg_file(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let bytes = std::fs::read(path)?;
+        Ok(Self::from_reg(&decode_text(&bytes))?)
+    }
+
+    /// Parses the contents of a `.reg` export. Keys under hives other than
+    /// HKLM and HKCU are skipped.
+    pub fn from_reg(content: &str) -> Result<Self, RegFileError> {
+        let mut registry = Self::new();
+        let mut current: Option<(RootKey, String)> = None;
+
+        for (line_no, line) in logical_lines(content) {
+            let error = |message: String| RegFileError { line: line_no, message };
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(';') || line == "REGEDIT4" || line.starts_with("Windows Registry Editor") {
+                continue;
+            }
+
+            if let Some(section) = line.strip_prefix('[') {
+                let section = section.strip_suffix(']').ok_or_else(|| error("unterminated key".into()))?;
+                let (delete, section) = match section.strip_prefix('-') {
+                    Some(rest) => (true, rest),
+                    None => (false, section),
+                };
+                let (hive, path) = section.split_once('\\').unwrap_or((section, ""));
+
+                current = RootKey::from_name(hive).map(|root| (root, path.to_string()));
+                if let Some((root, path)) = &current {
+                    if delete {
+                        registry.delete_key(*root, path);
+                        current = None;
+                    } else {
+                        registry.create_key(*root, path);
+                    }
+                }
+                continue;
+            }
+
+            let Some((root, path)) = &current else {
+                continue;
+            };
+
+            let (name, data) = split_value_line(line).map_err(error)?;
+            match parse_data(data).map_err(error)? {
+                Some(value) => registry.set_value(*root, path, &name, value),
+                None => registry.delete_value(*root, path, &name),
+            }
+        }
+
+        Ok(registry)
+    }
+}
+
+impl RegistrySource for MemoryRegistry {
+    fn subkeys(&self, root: RootKey, path: &str) -> Vec<String> {
+        let path = normalize_path(path);
+        let prefix = if path.is_empty() { String::new() } else { format!(r"{}\", path) };
+
+        self.keys
+            .iter()
+            .filter(|((r, p), _)| *r == root && p.starts_with(&prefix) && !p[prefix.len()..].contains('\\'))
+            .filter(|((_, p), _)| p.len() > prefix.len())
+            .map(|(_, key)| key.name.clone())
+            .collect()
+    }
+
+    fn value(&self, root: RootKey, path: &str, name: &str) -> Option<RegValue> {
+        self.keys.get(&(root, normalize_path(path)))?.values.get(&name.to_lowercase()).cloned()
+    }
+}
+
+fn normalize_path(path: &str) -> String {
+    path.split('\\').filter(|p| !p.is_empty()).collect::<Vec<_>>().join("\\").to_lowercase()
+}
+
+/// `regedit` writes UTF-16LE with a BOM; older exports are 8-bit text.
+pub(crate) fn decode_text(bytes: &[u8]) -> String {
+    match bytes {
+        [0xff, 0xfe, rest @ ..] => {
+            let units: Vec<u16> = rest.chunks_exact(2).map(|pair| u16::from_le_bytes([pair[0], pair[1]])).collect();
+            String::from_utf16_lossy(&units)
+        }
+        [0xef, 0xbb, 0xbf, rest @ ..] => String::from_utf8_lossy(rest).into_owned(),
+        _ => String::from_utf8_lossy(bytes).into_owned(),
+    }
+}
+
+/// Joins lines continued with a trailing backslash, as used for long hex
+/// values, keeping the number of the first line.
+pub(crate) fn logical_lines(content: &str) -> Vec<(usize, String)> {
+    let mut lines = Vec::new();
+    let mut pending: Option<(usize, String)> = None;
+
+    for (index, raw) in content.lines().enumerate() {
+        let (line_no, mut line) = match pending.take() {
+            Some((line_no, mut joined)) => {
+                joined.push_str(raw.trim_start());
+                (line_no, joined)
+            }
+            None => (index + 1, raw.to_string()),
+        };
+
+        let is_continued = line.trim_end().ends_with('\\') && !line.trim_start().starts_with('[') && is_hex_line(&line);
+        if is_continued {
+            let trimmed_len = line.trim_end().len() - 1;
+            line.truncate(trimmed_len);
+            pending = Some((line_no, line));
+        } else {
+            lines.push((line_no, line));
+        }
+    }
+    lines.extend(pending);
+    lines
+}
+
+/// Only hex data may be continued; a string value can legitimately end in a
+/// backslash.
+fn is_hex_line(line: &str) -> bool {
+    split_value_line(line.trim())
+        .map(|(_, data)| data.starts_with("hex"))
+        .unwrap_or(false)
+}
+
+/// Splits `"Name"=data` or `@=data` into the unescaped name and raw data.
+pub(crate) fn split_value_line(line: &str) -> Result<(String, &str), String> {
+    if let Some(data) = line.strip_prefix("@=") {
+        return Ok((String::new(), data));
+    }
+
+    let (name, rest) = parse_quoted(line).ok_or_else(|| format!("expected a value name: {}", line))?;
+    let data = rest.strip_prefix('=').ok_or_else(|| format!("expected '=' after \"{}\"", name))?;
+    Ok((name, data.trim()))
+}
+
+/// Parses a leading `"..."` with `\\` and `\"` escapes, returning the
+/// string and whatever follows the closing quote.
+pub(crate) fn parse_quoted(input: &str) -> Option<(String, &str)> {
+    let body = input.strip_prefix('"')?;
+    let mut value = String::new();
+    let mut chars = body.char_indices();
+
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '\\' => match chars.next()?.1 {
+                'n' => value.push('\n'),
+                '0' => value.push('\0'),
+                other => value.push(other),
+            },
+            '"' => return Some((value, &body[index + 1..])),
+            other => value.push(other),
+        }
+    }
+    None
+}
+
+/// Parses the data part of a value line. `Ok(None)` means the value is
+/// deleted (`=-`).
+pub(crate) fn parse_data(data: &str) -> Result<Option<RegValue>, String> {
+    if data == "-" {
+        return Ok(None);
+    }
+
+    if data.starts_with('"') {
+        let (value, _) = parse_quoted(data).ok_or_else(|| format!("unterminated string: {}", data))?;
+        return Ok(Some(RegValue::String(value)));
+    }
+
+    if let Some(hex) = data.strip_prefix("dword:") {
+        let value = u32::from_str_radix(hex.trim(), 16).map_err(|e| format!("bad dword {}: {}", hex, e))?;
+        return Ok(Some(RegValue::Dword(value)));
+    }
+
+    let (kind, bytes) = if let Some(bytes) = data.strip_prefix("hex:") {
+        (3, bytes)
+    } else if let Some(rest) = data.strip_prefix("hex(") {
+        let (kind, bytes) = rest.split_once("):").ok_or_else(|| format!("bad hex type: {}", data))?;
+        (u32::from_str_radix(kind, 16).map_err(|e| format!("bad hex type {}: {}", kind, e))?, bytes)
+    } else {
+        return Err(format!("unsupported value data: {}", data));
+    };
+
+    let bytes = parse_hex_bytes(bytes)?;
+    Ok(Some(typed_value(kind, bytes)))
+}
+
+fn parse_hex_bytes(input: &str) -> Result<Vec<u8>, String> {
+    input
+        .split(',')
+        .map(str::trim)
+        .filter(|b| !b.is_empty())
+        .map(|b| u8::from_str_radix(b, 16).map_err(|e| format!("bad hex byte {}: {}", b, e)))
+        .collect()
+}
+
+/// Interprets raw bytes by registry type number (`REG_SZ` = 1, ...).
+pub(crate) fn typed_value(kind: u32, bytes: Vec<u8>) -> RegValue {
+    let utf16 = |bytes: &[u8]| -> Vec<u16> {
+        bytes.chunks_exact(2).map(|pair| u16::from_le_bytes([pair[0], pair[1]])).collect()
+    };
+    let until_nul = |units: &[u16]| {
+        let end = units.iter().position(|&u| u == 0).unwrap_or(units.len());
+        String::from_utf16_lossy(&units[..end])
+    };
+
+    match kind {
+        1 => RegValue::String(until_nul(&utf16(&bytes))),
+        2 => RegValue::ExpandString(until_nul(&utf16(&bytes))),
+        4 if bytes.len() >= 4 => RegValue::Dword(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])),
+        7 => RegValue::MultiString(
+            utf16(&bytes)
+                .split(|&u| u == 0)
+                .filter(|s| !s.is_empty())
+                .map(String::from_utf16_lossy)
+                .collect(),
+        ),
+        11 if bytes.len() >= 8 => {
+            let mut raw = [0u8; 8];
+            raw.copy_from_slice(&bytes[..8]);
+            RegValue::Qword(u64::from_le_bytes(raw))
+        }
+        _ => RegValue::Binary(bytes),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORT: &str = r#"Windows Registry Editor Version 5.00
+
+[HKEY_LOCAL_MACHINE\SOFTWARE\WOW6432Node\Ubisoft\Launcher\Installs\4311]
+"InstallDir"="D:\\Games\\Ubisoft\\Far Cry 6\\"
+
+[HKEY_CURRENT_USER\SOFTWARE\Amazon Games]
+"InstallPath"="C:\\Users\\me\\AppData\\Local\\Amazon Games\\App"
+@="default"
+"Enabled"=dword:0000002a
+"Size"=hex(b):00,10,00,00,00,00,00,00
+"Dirs"=hex(7):61,00,00,00,62,00,00,00,\
+  00,00
+"Path"=hex(2):25,00,41,00,00,00
+
+[HKEY_CLASSES_ROOT\.exe]
+@="exefile"
+
+[HKEY_CURRENT_USER\SOFTWARE\Removed]
+"Gone"="yes"
+
+[-HKEY_CURRENT_USER\SOFTWARE\Removed]
+"#;
+
+    #[test]
+    fn parses_reg_export() {
+        let registry = MemoryRegistry::from_reg(EXPORT).unwrap();
+
+        assert_eq!(
+            registry.subkeys(RootKey::LocalMachine, r"software\wow6432node\ubisoft\launcher\installs"),
+            vec!["4311"]
+        );
+        assert_eq!(
+            registry.string(RootKey::LocalMachine, r"SOFTWARE\WOW6432Node\Ubisoft\Launcher\Installs\4311", "InstallDir"),
+            Some(r"D:\Games\Ubisoft\Far Cry 6\".to_string())
+        );
+
+        let amazon = r"SOFTWARE\Amazon Games";
+        assert_eq!(registry.string(RootKey::CurrentUser, amazon, ""), Some("default".to_string()));
+        assert_eq!(registry.value(RootKey::CurrentUser, amazon, "enabled"), Some(RegValue::Dword(42)));
+        assert_eq!(registry.value(RootKey::CurrentUser, amazon, "Size"), Some(RegValue::Qword(4096)));
+        assert_eq!(
+            registry.value(RootKey::CurrentUser, amazon, "Dirs"),
+            Some(RegValue::MultiString(vec!["a".to_string(), "b".to_string()]))
+        );
+        assert_eq!(registry.string(RootKey::CurrentUser, amazon, "Path"), Some("%A".to_string()));
+
+        let mut software = registry.subkeys(RootKey::CurrentUser, "SOFTWARE");
+        software.sort();
+        assert_eq!(software, vec!["Amazon Games"]);
+    }
+
+    #[test]
+    fn reports_malformed_lines() {
+        let err = MemoryRegistry::from_reg("[HKEY_CURRENT_USER\\Software]\n\"Name\"=dword:xyz\n").unwrap_err();
+        assert_eq!(err.line, 2);
+    }
+
+    #[test]
+    fn decodes_utf16_exports() {
+        let mut bytes = vec![0xff, 0xfe];
+        bytes.extend("REGEDIT4\r\n".encode_utf16().flat_map(|u| u.to_le_bytes()));
+        assert_eq!(decode_text(&bytes), "REGEDIT4\r\n");
+    }
+}
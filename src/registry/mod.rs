@@ -0,0 +1,71 @@
+pub mod hive;
+pub mod memory;
+pub mod wine;
+#[cfg(target_os = "windows")]
+pub mod windows;
+
+/// Registry hives the detectors read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum RootKey {
+    LocalMachine,
+    CurrentUser,
+}
+
+impl RootKey {
+    /// Parses a hive name as written in `.reg` files, long or short form.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "HKEY_LOCAL_MACHINE" | "HKLM" => Some(RootKey::LocalMachine),
+            "HKEY_CURRENT_USER" | "HKCU" => Some(RootKey::CurrentUser),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RegValue {
+    String(String),
+    ExpandString(String),
+    MultiString(Vec<String>),
+    Dword(u32),
+    Qword(u64),
+    Binary(Vec<u8>),
+}
+
+/// Read-only access to a Windows-style registry. Key paths are
+/// backslash-separated and, like on Windows, matched case-insensitively.
+pub trait RegistrySource {
+    /// Names of the immediate subkeys of `path`, empty if it does not exist.
+    fn subkeys(&self, root: RootKey, path: &str) -> Vec<String>;
+
+    fn value(&self, root: RootKey, path: &str, name: &str) -> Option<RegValue>;
+
+    fn string(&self, root: RootKey, path: &str, name: &str) -> Option<String> {
+        match self.value(root, path, name)? {
+            RegValue::String(s) | RegValue::ExpandString(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+/// The registry of the machine we run on. Other platforms have none, which
+/// reads as an empty registry.
+pub fn system_registry() -> Box<dyn RegistrySource> {
+    #[cfg(target_os = "windows")]
+    {
+        Box::new(windows::WindowsRegistry)
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        Box::new(memory::MemoryRegistry::new())
+    }
+}
+
+/// Joins registry key paths with a backslash.
+pub fn join_key(parent: &str, child: &str) -> String {
+    if parent.is_empty() {
+        child.to_string()
+    } else {
+        format!(r"{}\{}", parent.trim_end_matches('\\'), child)
+    }
+}
@@ -0,0 +1,62 @@
+use winreg::enums::*;
+use winreg::{RegKey, RegValue as RawValue};
+use crate::registry::{RegValue, RegistrySource, RootKey};
+
+/// The live registry, read through the Win32 API.
+pub struct WindowsRegistry;
+
+impl WindowsRegistry {
+    fn open(root: RootKey, path: &str) -> Option<RegKey> {
+        let predef = match root {
+            RootKey::LocalMachine => HKEY_LOCAL_MACHINE,
+            RootKey::CurrentUser => HKEY_CURRENT_USER,
+        };
+        RegKey::predef(predef).open_subkey(path).ok()
+    }
+}
+
+impl RegistrySource for WindowsRegistry {
+    fn subkeys(&self, root: RootKey, path: &str) -> Vec<String> {
+        Self::open(root, path)
+            .map(|key| key.enum_keys().flatten().collect())
+            .unwrap_or_default()
+    }
+
+    fn value(&self, root: RootKey, path: &str, name: &str) -> Option<RegValue> {
+        let raw = Self::open(root, path)?.get_raw_value(name).ok()?;
+        Some(convert_value(raw))
+    }
+}
+
+fn convert_value(raw: RawValue) -> RegValue {
+    match raw.vtype {
+        REG_SZ => RegValue::String(decode_utf16(&raw.bytes)),
+        REG_EXPAND_SZ => RegValue::ExpandString(decode_utf16(&raw.bytes)),
+        REG_MULTI_SZ => RegValue::MultiString(
+            decode_utf16_units(&raw.bytes)
+                .split(|&unit| unit == 0)
+                .filter(|s| !s.is_empty())
+                .map(String::from_utf16_lossy)
+                .collect(),
+        ),
+        REG_DWORD if raw.bytes.len() >= 4 => {
+            RegValue::Dword(u32::from_le_bytes([raw.bytes[0], raw.bytes[1], raw.bytes[2], raw.bytes[3]]))
+        }
+        REG_QWORD if raw.bytes.len() >= 8 => {
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(&raw.bytes[..8]);
+            RegValue::Qword(u64::from_le_bytes(bytes))
+        }
+        _ => RegValue::Binary(raw.bytes),
+    }
+}
+
+fn decode_utf16_units(bytes: &[u8]) -> Vec<u16> {
+    bytes.chunks_exact(2).map(|pair| u16::from_le_bytes([pair[0], pair[1]])).collect()
+}
+
+fn decode_utf16(bytes: &[u8]) -> String {
+    let units = decode_utf16_units(bytes);
+    let end = units.iter().position(|&unit| unit == 0).unwrap_or(units.len());
+    String::from_utf16_lossy(&units[..end])
+}
@@ -0,0 +1,192 @@
+use std::path::Path;
+use crate::registry::memory::{decode_text, logical_lines, parse_data, MemoryRegistry, RegFileError};
+use crate::registry::{RegValue, RootKey};
+
+/// Loads a Wine prefix's registry: `system.reg` as HKLM and `user.reg` as
+/// HKCU. A missing `user.reg` reads as an empty HKCU.
+pub fn load_prefix_registry(prefix: &Path) -> Result<MemoryRegistry, Box<dyn std::error::Error>> {
+    let mut registry = MemoryRegistry::new();
+
+    let system = std::fs::read(prefix.join("system.reg"))?;
+    parse_wine_registry(&mut registry, RootKey::LocalMachine, &decode_text(&system))?;
+
+    if let Ok(user) = std::fs::read(prefix.join("user.reg")) {
+        parse_wine_registry(&mut registry, RootKey::CurrentUser, &decode_text(&user))?;
+    }
+
+    Ok(registry)
+}
+
+/// Adds the keys of one Wine registry file (`WINE REGISTRY Version 2`) to
+/// `registry` under `root`. Key paths in these files are relative to the
+/// hive, e.g. `[Software\\Valve\\Steam] 1700000000`, and backslashes in key
+/// and value names are escaped.
+pub fn parse_wine_registry(registry: &mut MemoryRegistry, root: RootKey, content: &str) -> Result<(), RegFileError> {
+    let mut current: Option<String> = None;
+
+    for (line_no, line) in logical_lines(content) {
+        let error = |message: String| RegFileError { line: line_no, message };
+        let line = line.trim();
+        // `#time=`, `#class=` and `#arch=` lines carry metadata we do not need.
+        if line.is_empty() || line.starts_with(';') || line.starts_with('#') || line.starts_with("WINE REGISTRY") {
+            continue;
+        }
+
+        if let Some(section) = line.strip_prefix('[') {
+            let end = section.rfind(']').ok_or_else(|| error("unterminated key".into()))?;
+            let path = unescape(&section[..end]).map_err(error)?;
+            registry.create_key(root, &path);
+            current = Some(path);
+            continue;
+        }
+
+        let Some(path) = &current else {
+            continue;
+        };
+
+        let (name, data) = if let Some(data) = line.strip_prefix("@=") {
+            (String::new(), data)
+        } else {
+            let (name, rest) = parse_string(line).map_err(error)?;
+            let data = rest.strip_prefix('=').ok_or_else(|| error(format!("expected '=' after \"{}\"", name)))?;
+            (name, data)
+        };
+
+        if let Some(value) = parse_value(data.trim()).map_err(error)? {
+            registry.set_value(root, path, &name, value);
+        }
+    }
+
+    Ok(())
+}
+
+/// Wine writes strings as `"..."`, expandable and multi strings as
+/// `str(2):"..."` and `str(7):"..."`, and everything else like `regedit`.
+fn parse_value(data: &str) -> Result<Option<RegValue>, String> {
+    if data.starts_with('"') {
+        return Ok(Some(RegValue::String(parse_string(data)?.0)));
+    }
+    if let Some(rest) = data.strip_prefix("str(2):") {
+        return Ok(Some(RegValue::ExpandString(parse_string(rest)?.0)));
+    }
+    if let Some(rest) = data.strip_prefix("str(7):") {
+        let strings = parse_string(rest)?.0.split('\0').filter(|s| !s.is_empty()).map(|s| s.to_string()).collect();
+        return Ok(Some(RegValue::MultiString(strings)));
+    }
+    parse_data(data)
+}
+
+/// Parses a leading quoted string, returning it and what follows the
+/// closing quote.
+fn parse_string(input: &str) -> Result<(String, &str), String> {
+    let body = input.strip_prefix('"').ok_or_else(|| format!("expected a string: {}", input))?;
+    let mut escaped = false;
+    for (index, c) in body.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return Ok((unescape(&body[..index])?, &body[index + 1..])),
+            _ => {}
+        }
+    }
+    Err(format!("unterminated string: {}", input))
+}
+
+/// Resolves the C-style escapes Wine uses: `\\`, `\"`, `\n`, `\r`, `\t`,
+/// `\0`, `\xHHHH` and octal `\ooo`.
+fn unescape(input: &str) -> Result<String, String> {
+    let mut output = String::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            output.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => output.push('\n'),
+            Some('r') => output.push('\r'),
+            Some('t') => output.push('\t'),
+            Some('x') => {
+                let mut code = 0u32;
+                let mut digits = 0;
+                while digits < 4 && let Some(digit) = chars.peek().and_then(|c| c.to_digit(16)) {
+                    code = code * 16 + digit;
+                    digits += 1;
+                    chars.next();
+                }
+                output.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+            }
+            Some(first @ '0'..='7') => {
+                let mut code = first.to_digit(8).unwrap_or(0);
+                for _ in 0..2 {
+                    match chars.peek().and_then(|c| c.to_digit(8)) {
+                        Some(digit) => {
+                            code = code * 8 + digit;
+                            chars.next();
+                        }
+                        None => break,
+                    }
+                }
+                output.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+            }
+            Some(other) => output.push(other),
+            None => return Err(format!("dangling escape in {}", input)),
+        }
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use crate::registry::RegistrySource;
+
+    fn fixture_dir() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join("wine")
+    }
+
+    #[test]
+    fn loads_system_and_user_registry() {
+        let registry = load_prefix_registry(&fixture_dir().join("prefix")).unwrap();
+
+        let installs = r"Software\Wow6432Node\Ubisoft\Launcher\Installs";
+        assert_eq!(registry.subkeys(RootKey::LocalMachine, installs), vec!["5266"]);
+        assert_eq!(
+            registry.string(RootKey::LocalMachine, &format!(r"{}\5266", installs), "InstallDir"),
+            Some("C:/Program Files (x86)/Ubisoft/Ubisoft Game Launcher/games/Far Cry 6/".to_string())
+        );
+
+        let uninstall = r"SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall\Hollow Knight_is1";
+        assert_eq!(
+            registry.string(RootKey::LocalMachine, uninstall, "InstallLocation"),
+            Some(r"C:\Games\Hollow Knight".to_string())
+        );
+        assert_eq!(registry.string(RootKey::LocalMachine, uninstall, "Comments"), Some("Café \"Hallownest\"\n".to_string()));
+        assert_eq!(registry.value(RootKey::LocalMachine, uninstall, "EstimatedSize"), Some(RegValue::Dword(9_437_184)));
+        assert_eq!(
+            registry.value(RootKey::LocalMachine, uninstall, "Paths"),
+            Some(RegValue::MultiString(vec!["a".to_string(), "b".to_string()]))
+        );
+        assert_eq!(
+            registry.value(RootKey::LocalMachine, uninstall, "Icon"),
+            Some(RegValue::ExpandString(r"%ProgramFiles%\hk.ico".to_string()))
+        );
+
+        assert_eq!(
+            registry.string(RootKey::CurrentUser, r"Software\Amazon Games", "InstallPath"),
+            Some(r"C:\users\steamuser\AppData\Local\Amazon Games\App".to_string())
+        );
+    }
+
+    #[test]
+    fn reports_the_failing_line() {
+        let mut registry = MemoryRegistry::new();
+        let error = parse_wine_registry(&mut registry, RootKey::LocalMachine, "WINE REGISTRY Version 2\n\n[Software] 1\n\"Bad\"=what\n")
+            .unwrap_err();
+        assert_eq!(error.line, 4);
+    }
+}
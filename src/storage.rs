@@ -1,10 +1,10 @@
 use std::collections::HashMap;
-use std::fs::File;
+use std::fs::{File, OpenOptions};
 use std::io::Write;
 use serde_json::to_string_pretty;
-use crate::models::GameSession;
+use crate::models::{GameSession, SessionEvent, SessionKey};
 
-pub fn save_sessions_to_json(sessions: &HashMap<u32, GameSession>) -> std::io::Result<()> {
+pub fn save_sessions_to_json(sessions: &HashMap<SessionKey, GameSession>) -> std::io::Result<()> {
     let session_list: Vec<&GameSession> = sessions.values().collect();
     let json_data = to_string_pretty(&session_list)?;
 
@@ -12,3 +12,16 @@ pub fn save_sessions_to_json(sessions: &HashMap<u32, GameSession>) -> std::io::R
     file.write_all(json_data.as_bytes())?;
     Ok(())
 }
+
+/// Appends `events` to `session_events.jsonl`, one JSON object per line, so
+/// finished sessions outlive `active_sessions.json`.
+pub fn append_session_events(events: &[SessionEvent]) -> std::io::Result<()> {
+    let mut lines = String::new();
+    for event in events {
+        lines.push_str(&serde_json::to_string(event)?);
+        lines.push('\n');
+    }
+
+    let mut file = OpenOptions::new().create(true).append(true).open("session_events.jsonl")?;
+    file.write_all(lines.as_bytes())
+}
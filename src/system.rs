@@ -1,31 +1,239 @@
-use wmi::{COMLibrary, WMIConnection};
+#[cfg(target_os = "windows")]
+use wmi::{COMLibrary, WMIConnection, WMIDateTime};
+#[cfg(target_os = "windows")]
 use serde::Deserialize;
+#[cfg(target_os = "windows")]
+use chrono::{DateTime, Local};
+#[cfg(target_os = "windows")]
+use std::cell::RefCell;
+#[cfg(target_os = "windows")]
+use std::collections::HashMap;
+#[cfg(target_os = "windows")]
+use std::path::PathBuf;
 use crate::models::ProcessInfo;
 
+/// Lists the processes running on a machine.
+pub trait ProcessSource {
+    fn processes(&self) -> Result<Vec<ProcessInfo>, Box<dyn std::error::Error>>;
+}
+
+/// The process list of the machine we run on: WMI on Windows, `/proc` on
+/// Linux.
+pub fn system_process_source() -> Result<Box<dyn ProcessSource>, Box<dyn std::error::Error>> {
+    #[cfg(target_os = "windows")]
+    {
+        Ok(Box::new(WmiProcessSource::new()?))
+    }
+    #[cfg(target_os = "linux")]
+    {
+        Ok(Box::new(ProcFsProcessSource))
+    }
+    #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+    {
+        Err("listing processes is not supported on this platform".into())
+    }
+}
+
+#[cfg(target_os = "windows")]
 #[allow(non_camel_case_types, non_snake_case)]
 #[derive(Deserialize, Debug)]
-struct Win32_Process {
+pub(crate) struct Win32_Process {
     Name: String,
-    ProcessId: u32,
+    pub(crate) ProcessId: u32,
+    ParentProcessId: u32,
+    // Null for processes of other users unless we run elevated.
+    ExecutablePath: Option<String>,
+    CommandLine: Option<String>,
+    CreationDate: Option<WMIDateTime>,
 }
 
-pub struct SystemScanner {
+#[cfg(target_os = "windows")]
+pub struct WmiProcessSource {
     wmi_con: WMIConnection,
+    /// Process owners by pid and creation time. Looking one up opens the
+    /// process token, which is too slow to repeat every second.
+    owners: RefCell<HashMap<(u32, Option<DateTime<Local>>), Option<String>>>,
 }
 
-impl SystemScanner {
+#[cfg(target_os = "windows")]
+impl WmiProcessSource {
     pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
         let com_con = COMLibrary::new()?;
         let wmi_con = WMIConnection::new(com_con.into())?;
-        Ok(Self { wmi_con })
+        Ok(Self { wmi_con, owners: RefCell::new(HashMap::new()) })
     }
+}
 
-    pub fn get_running_processes(&self) -> Result<Vec<ProcessInfo>, Box<dyn std::error::Error>> {
+#[cfg(target_os = "windows")]
+impl ProcessSource for WmiProcessSource {
+    fn processes(&self) -> Result<Vec<ProcessInfo>, Box<dyn std::error::Error>> {
         let processes: Vec<Win32_Process> = self.wmi_con.query()?;
-        
-        Ok(processes.into_iter().map(|p| ProcessInfo {
-            name: p.Name,
-            pid: p.ProcessId,
-        }).collect())
+        let mut known_owners = self.owners.borrow_mut();
+        let mut owners = HashMap::new();
+
+        let processes = processes.into_iter().map(|p| {
+            let key = (p.ProcessId, p.CreationDate.as_ref().map(|date| date.0.with_timezone(&Local)));
+            let user = known_owners.remove(&key).unwrap_or_else(|| process_owner(p.ProcessId));
+            owners.insert(key, user.clone());
+            wmi_process_info(p, user)
+        }).collect();
+
+        *known_owners = owners;
+        Ok(processes)
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub(crate) fn wmi_process_info(p: Win32_Process, user: Option<String>) -> ProcessInfo {
+    ProcessInfo {
+        name: p.Name,
+        pid: p.ProcessId,
+        steam_app_id: None,
+        windows_exe: None,
+        exe_path: p.ExecutablePath.map(PathBuf::from),
+        argv: p.CommandLine.as_deref().map(split_command_line).unwrap_or_default(),
+        parent_pid: Some(p.ParentProcessId).filter(|&ppid| ppid != 0),
+        user,
+        start_time: p.CreationDate.map(|date| date.0.with_timezone(&Local)),
+    }
+}
+
+/// The name of the account a process runs as, read from its access token.
+#[cfg(target_os = "windows")]
+pub(crate) fn process_owner(pid: u32) -> Option<String> {
+    use windows::core::{PCWSTR, PWSTR};
+    use windows::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows::Win32::Security::{GetTokenInformation, LookupAccountSidW, TokenUser, SID_NAME_USE, TOKEN_QUERY, TOKEN_USER};
+    use windows::Win32::System::Threading::{OpenProcess, OpenProcessToken, PROCESS_QUERY_LIMITED_INFORMATION};
+
+    unsafe {
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?;
+        let mut token = HANDLE::default();
+        let opened = OpenProcessToken(process, TOKEN_QUERY, &mut token);
+        let _ = CloseHandle(process);
+        opened.ok()?;
+
+        // TOKEN_USER is followed by the SID it points to; u64s keep it aligned.
+        let mut buffer = [0u64; 64];
+        let mut length = 0;
+        let read = GetTokenInformation(
+            token,
+            TokenUser,
+            Some(buffer.as_mut_ptr().cast()),
+            std::mem::size_of_val(&buffer) as u32,
+            &mut length,
+        );
+        let _ = CloseHandle(token);
+        read.ok()?;
+        let sid = (*buffer.as_ptr().cast::<TOKEN_USER>()).User.Sid;
+
+        let mut name = [0u16; 256];
+        let mut domain = [0u16; 256];
+        let mut name_len = name.len() as u32;
+        let mut domain_len = domain.len() as u32;
+        let mut sid_type = SID_NAME_USE::default();
+        LookupAccountSidW(
+            PCWSTR::null(),
+            sid,
+            PWSTR(name.as_mut_ptr()),
+            &mut name_len,
+            PWSTR(domain.as_mut_ptr()),
+            &mut domain_len,
+            &mut sid_type,
+        )
+        .ok()?;
+        Some(String::from_utf16_lossy(&name[..name_len as usize]))
+    }
+}
+
+/// Splits a Windows command line into arguments the way the C runtime
+/// does: whitespace separates arguments unless quoted, `\"` is a literal
+/// quote, and backslashes are only special before a quote.
+pub fn split_command_line(command_line: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quoted = false;
+    let mut backslashes = 0;
+
+    for c in command_line.chars() {
+        match c {
+            '\\' => {
+                backslashes += 1;
+                in_arg = true;
+                continue;
+            }
+            '"' => {
+                current.extend(std::iter::repeat_n('\\', backslashes / 2));
+                if backslashes % 2 == 1 {
+                    current.push('"');
+                } else {
+                    quoted = !quoted;
+                }
+                in_arg = true;
+            }
+            ' ' | '\t' if !quoted => {
+                current.extend(std::iter::repeat_n('\\', backslashes));
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                }
+                in_arg = false;
+            }
+            _ => {
+                current.extend(std::iter::repeat_n('\\', backslashes));
+                current.push(c);
+                in_arg = true;
+            }
+        }
+        backslashes = 0;
+    }
+
+    current.extend(std::iter::repeat_n('\\', backslashes));
+    if in_arg {
+        args.push(current);
+    }
+    args
+}
+
+#[cfg(target_os = "linux")]
+pub struct ProcFsProcessSource;
+
+#[cfg(target_os = "linux")]
+impl ProcessSource for ProcFsProcessSource {
+    fn processes(&self) -> Result<Vec<ProcessInfo>, Box<dyn std::error::Error>> {
+        Ok(crate::procfs::list_processes()?)
+    }
+}
+
+/// Plays back a fixed series of process lists, one per call, for driving
+/// the tracker in tests. Once the script runs out nothing is running.
+pub struct ScriptedProcessSource {
+    snapshots: std::cell::RefCell<std::collections::VecDeque<Vec<ProcessInfo>>>,
+}
+
+impl ScriptedProcessSource {
+    pub fn new(snapshots: Vec<Vec<ProcessInfo>>) -> Self {
+        Self { snapshots: std::cell::RefCell::new(snapshots.into()) }
+    }
+}
+
+impl ProcessSource for ScriptedProcessSource {
+    fn processes(&self) -> Result<Vec<ProcessInfo>, Box<dyn std::error::Error>> {
+        Ok(self.snapshots.borrow_mut().pop_front().unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_windows_command_lines() {
+        assert_eq!(
+            split_command_line(r#""C:\Program Files\Game\game.exe" -windowed  --name="Big Boss""#),
+            vec![r"C:\Program Files\Game\game.exe", "-windowed", "--name=Big Boss"]
+        );
+        assert_eq!(split_command_line(r#"a\\"b c" d\"e \\server\share"#), vec![r"a\b c", r#"d"e"#, r"\\server\share"]);
+        assert!(split_command_line("   ").is_empty());
     }
 }
@@ -1,57 +1,615 @@
 use std::collections::{HashMap, HashSet};
-use chrono::Local;
-use crate::models::{GameSession, ProcessInfo};
+use chrono::{DateTime, Duration, Local};
+use crate::library::GameLibrary;
+use crate::models::{DetectedGame, GameSession, ProcessId, ProcessInfo, SessionEvent, SessionGap, SessionKey, StartTimeSource};
+use crate::monitor::ProcessEvent;
+
+// Steam, Proton and Wine plumbing that inherits a game's SteamAppId without
+// being the game itself. Shells and Python are among them as launch scripts
+// use them, so none of these keep a session going once the game is gone.
+const LAUNCH_HELPER_PROCESSES: &[&str] = &[
+    "reaper", "steam-launch-wrapper", "steam-runtime-launcher-service", "pv-bwrap",
+    "srt-bwrap", "pressure-vessel-wrap", "proton", "python3", "python", "sh", "bash",
+    "wineserver", "wine", "wine64", "wine-preloader", "wine64-preloader", "gamescope",
+    "gameoverlayui", "fossilize_replay",
+];
+
+const WINE_SYSTEM_EXES: &[&str] = &[
+    "steam.exe", "services.exe", "explorer.exe", "winedevice.exe", "plugplay.exe",
+    "svchost.exe", "rpcss.exe", "tabtip.exe", "conhost.exe", "start.exe", "rundll32.exe",
+    "wineboot.exe", "winemenubuilder.exe",
+];
+
+/// How long a game may be gone before its session ends, unless configured
+/// otherwise. Covers a crash and relaunch, or a restart to apply settings.
+const DEFAULT_GRACE_PERIOD_SECONDS: i64 = 60;
 
 pub struct SessionTracker {
-    active_sessions: HashMap<u32, GameSession>,
-    game_cache: HashSet<String>,
+    active_sessions: HashMap<SessionKey, GameSession>,
+    /// Sessions whose processes all exited, kept for their game's grace
+    /// period in case it comes back.
+    suspended_sessions: Vec<(SessionKey, GameSession)>,
+    /// The running game processes by pid.
+    processes: HashMap<u32, TrackedProcess>,
+    library: GameLibrary,
+    grace_period: Duration,
+    /// Grace periods by store id or executable file name.
+    game_grace_periods: HashMap<String, Duration>,
+    events: Vec<SessionEvent>,
+    clock: Box<dyn Fn() -> DateTime<Local>>,
+}
+
+struct TrackedProcess {
+    start_time: Option<DateTime<Local>>,
+    session: SessionKey,
+    /// A launch helper, which stays in its session without keeping it
+    /// active, e.g. a terminal opened from the game.
+    helper: bool,
 }
 
 impl SessionTracker {
-    pub fn new(game_cache: HashSet<String>) -> Self {
+    pub fn new(games: Vec<DetectedGame>) -> Self {
         Self {
             active_sessions: HashMap::new(),
-            game_cache,
-        }
-    }
-
-    pub fn update(&mut self, processes: &[ProcessInfo]) -> &HashMap<u32, GameSession> {
-        let current_pids: HashSet<u32> = processes.iter().map(|p| p.pid).collect();
-        let now = Local::now();
-
-        for process in processes {
-            let p_name_lower = process.name.to_lowercase();
-
-            if self.game_cache.contains(&p_name_lower) {
-                self.active_sessions.entry(process.pid)
-                    .and_modify(|session| {
-                        session.last_seen = now;
-                        session.duration_seconds = (now - session.start_time).num_seconds();
-                    })
-                    .or_insert_with(|| {
-                        println!("🚀 GAME STARTED: {} (PID: {})", process.name, process.pid);
-                        GameSession {
-                            game_name: process.name.clone(),
-                            process_id: process.pid,
-                            start_time: now,
-                            last_seen: now,
-                            duration_seconds: 0,
-                            is_active: true,
-                        }
-                    });
+            suspended_sessions: Vec::new(),
+            processes: HashMap::new(),
+            library: GameLibrary::new(games),
+            grace_period: Duration::seconds(DEFAULT_GRACE_PERIOD_SECONDS),
+            game_grace_periods: HashMap::new(),
+            events: Vec::new(),
+            clock: Box::new(Local::now),
+        }
+    }
+
+    /// Reads the time from `clock` instead of the system clock.
+    pub fn with_clock(mut self, clock: impl Fn() -> DateTime<Local> + 'static) -> Self {
+        self.clock = Box::new(clock);
+        self
+    }
+
+    /// Sets how long games may be gone before their session ends, 60 seconds
+    /// unless set. Zero ends sessions as soon as their last process exits.
+    pub fn set_grace_period(&mut self, grace_period: Duration) {
+        self.grace_period = grace_period;
+    }
+
+    /// Overrides the grace period for one game, named by its store id, e.g.
+    /// a Steam AppID, or by its executable's file name.
+    pub fn set_game_grace_period(&mut self, game: &str, grace_period: Duration) {
+        self.game_grace_periods.insert(game.to_string(), grace_period);
+    }
+
+    /// Returns the sessions started, resumed and ended since the last call.
+    pub fn take_events(&mut self) -> Vec<SessionEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Replaces the games processes are matched against, e.g. after a game
+    /// was installed. The new library is built before the swap, so updates
+    /// always see either the old or the new set in full.
+    pub fn set_games(&mut self, games: Vec<DetectedGame>) {
+        self.library = GameLibrary::new(games);
+    }
+
+    /// Applies one event from a [`ProcessMonitor`](crate::monitor::ProcessMonitor).
+    pub fn handle_event(&mut self, event: &ProcessEvent) {
+        match event {
+            ProcessEvent::Started(process) => self.process_started(process, (self.clock)()),
+            ProcessEvent::Exited(process) => {
+                // Creation and exit notifications may arrive out of order
+                // when a pid is reused right away.
+                if self.processes.get(&process.pid).is_some_and(|known| same_start(known.start_time, process.start_time)) {
+                    self.process_exited(process.pid);
+                }
+            }
+            ProcessEvent::Snapshot(processes) => self.apply_snapshot(processes),
+        }
+    }
+
+    /// Treats `processes` as everything running: starts sessions for new
+    /// game processes and ends those whose processes are all gone.
+    pub fn update(&mut self, processes: &[ProcessInfo]) -> &HashMap<SessionKey, GameSession> {
+        self.apply_snapshot(processes);
+        self.refresh()
+    }
+
+    /// Brings the durations of running sessions up to date, and ends those
+    /// whose game stayed away past its grace period.
+    pub fn refresh(&mut self) -> &HashMap<SessionKey, GameSession> {
+        let now = (self.clock)();
+        for session in self.active_sessions.values_mut() {
+            session.last_seen = now;
+            session.duration_seconds = played_seconds(session, now);
+        }
+        self.end_expired_sessions(now);
+        &self.active_sessions
+    }
+
+    fn apply_snapshot(&mut self, processes: &[ProcessInfo]) {
+        let now = (self.clock)();
+        // Parents go first, so that a game's children join its session
+        // rather than start their own.
+        let mut pending: Vec<&ProcessInfo> = processes.iter().collect();
+        while !pending.is_empty() {
+            let waiting: HashSet<u32> = pending.iter().map(|p| p.pid).collect();
+            let (ready, rest): (Vec<_>, Vec<_>) = pending
+                .into_iter()
+                .partition(|p| !p.parent_pid.is_some_and(|ppid| ppid != p.pid && waiting.contains(&ppid)));
+            // Only a reused parent pid can close a cycle.
+            let stuck = ready.is_empty();
+            for process in if stuck { &rest } else { &ready } {
+                self.process_started(process, now);
+            }
+            pending = if stuck { Vec::new() } else { rest };
+        }
+
+        let running: HashSet<u32> = processes.iter().map(|p| p.pid).collect();
+        let exited: Vec<u32> = self.processes.keys().filter(|pid| !running.contains(pid)).copied().collect();
+        for pid in exited {
+            self.process_exited(pid);
+        }
+    }
+
+    fn process_started(&mut self, process: &ProcessInfo, now: DateTime<Local>) {
+        let exe_name = process.windows_exe.as_deref().unwrap_or(&process.name);
+        // The pid was freed and handed to a new process since we last looked.
+        if self.processes.get(&process.pid).is_some_and(|known| !same_start(known.start_time, process.start_time)) {
+            self.process_exited(process.pid);
+        }
+
+        // A process stays in its session even once its parent is gone and
+        // it would no longer be recognized on its own.
+        let Some(key) = self.processes.get(&process.pid).map(|known| known.session).or_else(|| self.session_key(process)) else {
+            return;
+        };
+        let helper = is_launch_helper(process);
+        let game = find_game(&self.library, process);
+        let steam_game = process
+            .steam_app_id
+            .and_then(|app_id| self.library.find_by_store_id("steam", &app_id.to_string()));
+
+        if !self.active_sessions.contains_key(&key) {
+            // Helpers join a running game, but never start or resume one.
+            // Nor do processes only known through their parent, like a
+            // command a wrapper script runs once the game has exited.
+            if helper {
+                self.processes.insert(process.pid, TrackedProcess { start_time: process.start_time, session: key, helper });
+            }
+            if helper || (steam_game.or(game).is_none() && process.steam_app_id.is_none()) {
+                return;
             }
         }
+        self.processes.insert(process.pid, TrackedProcess { start_time: process.start_time, session: key, helper });
+        // Clock skew between the kernel and us must not put a start ahead.
+        let created = process.start_time.map(|start_time| start_time.min(now));
+        let title = steam_game.or(game).map_or(exe_name, |g| g.title.as_str());
+        let source = steam_game.or(game).map(|g| g.source.clone());
+        let store_id = steam_game.or(game)
+            .and_then(|g| g.store_id.clone())
+            .or_else(|| process.steam_app_id.map(|app_id| app_id.to_string()));
+
+        // Only the same game resumes a session, whichever process it is.
+        if !self.active_sessions.contains_key(&key)
+            && let Some(index) = self.suspended_sessions
+                .iter()
+                .position(|(_, session)| plays(session, source.as_deref(), title))
+        {
+            let (_, mut session) = self.suspended_sessions.remove(index);
+            let gap = SessionGap { start: session.last_seen, end: created.unwrap_or(now).max(session.last_seen) };
+            println!(
+                "⏯️ GAME RESUMED: {} (PID: {}, away {} sec)",
+                session.game_name, process.pid, (gap.end - gap.start).num_seconds()
+            );
+            session.gaps.push(gap);
+            session.process_id = process.pid;
+            session.last_seen = now;
+            self.events.push(SessionEvent::Resumed(session.clone()));
+            self.active_sessions.insert(key, session);
+        }
+
+        self.active_sessions.entry(key)
+            .and_modify(|session| {
+                // A Steam app may spawn a launcher before the game itself;
+                // once a known executable shows up, attribute the session to it.
+                if game.is_some() && self.library.find_by_executable(&session.executable).is_none() {
+                    session.executable = exe_name.to_string();
+                    session.process_id = process.pid;
+                    if session.source.is_none() {
+                        session.game_name = title.to_string();
+                        session.source = source.clone();
+                        session.store_id = store_id.clone();
+                    }
+                }
+                // Processes of a snapshot are not seen in the order they started.
+                if let Some(created) = created
+                    && created < session.start_time
+                {
+                    session.start_time = created;
+                    session.start_time_source = StartTimeSource::ProcessCreation;
+                }
+            })
+            .or_insert_with(|| {
+                match process.steam_app_id {
+                    Some(app_id) => println!("🚀 GAME STARTED: {} (AppID: {}, PID: {})", title, app_id, process.pid),
+                    None => println!("🚀 GAME STARTED: {} (PID: {})", title, process.pid),
+                }
+                let session = GameSession {
+                    game_name: title.to_string(),
+                    executable: exe_name.to_string(),
+                    source,
+                    store_id,
+                    process_id: process.pid,
+                    steam_app_id: process.steam_app_id,
+                    start_time: created.unwrap_or(now),
+                    start_time_source: match created {
+                        Some(_) => StartTimeSource::ProcessCreation,
+                        None => StartTimeSource::FirstSeen,
+                    },
+                    last_seen: now,
+                    duration_seconds: 0,
+                    is_active: true,
+                    gaps: Vec::new(),
+                };
+                self.events.push(SessionEvent::Started(session.clone()));
+                session
+            });
+    }
+
+    /// The session `process` belongs to: its Steam app's, its parent's, so
+    /// that bootstrappers, launchers and Proton's `reaper` share one with the
+    /// game they start, or the one already running its game. `None` for
+    /// processes that are not part of a game.
+    fn session_key(&self, process: &ProcessInfo) -> Option<SessionKey> {
+        if let Some(app_id) = process.steam_app_id
+            && !is_launch_helper(process)
+        {
+            return Some(SessionKey::SteamApp(app_id));
+        }
+        // A parent that started after its child is a new process that
+        // reused the parent's pid.
+        if let Some(parent) = process.parent_pid.and_then(|ppid| self.processes.get(&ppid))
+            && parent.start_time.zip(process.start_time).is_none_or(|(parent, child)| parent <= child)
+        {
+            return Some(parent.session);
+        }
 
-        self.active_sessions.retain(|pid, session| {
-            if !current_pids.contains(pid) {
-                session.is_active = false;
-                println!("🛑 GAME ENDED: {} (Duration: {} sec)", session.game_name, session.duration_seconds);
-                return false; 
+        let game = find_game(&self.library, process)?;
+        let running = self.active_sessions
+            .iter()
+            .find(|(_, session)| plays(session, Some(&game.source), &game.title));
+        Some(running.map_or(SessionKey::Process(ProcessId::of(process)), |(&key, _)| key))
+    }
+
+    /// Suspends the session `pid` belonged to once only launch helpers, or
+    /// none of its processes, run.
+    fn process_exited(&mut self, pid: u32) {
+        let Some(TrackedProcess { session: key, .. }) = self.processes.remove(&pid) else {
+            return;
+        };
+        if let Some((&other, _)) = self.processes.iter().find(|(_, other)| other.session == key && !other.helper) {
+            // The game outlives the process that started it.
+            if let Some(session) = self.active_sessions.get_mut(&key)
+                && session.process_id == pid
+            {
+                session.process_id = other;
             }
-            true
+            return;
+        }
+
+        if let Some(mut session) = self.active_sessions.remove(&key) {
+            let now = (self.clock)();
+            session.last_seen = now;
+            session.duration_seconds = played_seconds(&session, now);
+            self.suspended_sessions.push((key, session));
+            self.end_expired_sessions(now);
+        }
+    }
+
+    fn grace_period(&self, session: &GameSession) -> Duration {
+        let by_store_id = session.store_id.as_ref().and_then(|id| self.game_grace_periods.get(id));
+        let by_executable = || {
+            self.game_grace_periods
+                .iter()
+                .find(|(game, _)| game.eq_ignore_ascii_case(&session.executable))
+                .map(|(_, grace_period)| grace_period)
+        };
+        by_store_id.or_else(by_executable).copied().unwrap_or(self.grace_period)
+    }
+
+    fn end_expired_sessions(&mut self, now: DateTime<Local>) {
+        let (expired, suspended): (Vec<_>, Vec<_>) = std::mem::take(&mut self.suspended_sessions)
+            .into_iter()
+            .partition(|(_, session)| now - session.last_seen >= self.grace_period(session));
+        self.suspended_sessions = suspended;
+
+        for (key, mut session) in expired {
+            // Only helpers can be left, e.g. a wrapper script's shell.
+            self.processes.retain(|_, process| process.session != key);
+            session.is_active = false;
+            println!("🛑 GAME ENDED: {} (Duration: {} sec)", session.game_name, session.duration_seconds);
+            self.events.push(SessionEvent::Ended(session));
+        }
+    }
+}
+
+/// Whether `session` is of the game with this launcher and title. Sessions
+/// of programs no launcher knows are never taken for one another.
+fn plays(session: &GameSession, source: Option<&str>, title: &str) -> bool {
+    source.is_some() && session.source.as_deref() == source && session.game_name == title
+}
+
+fn played_seconds(session: &GameSession, now: DateTime<Local>) -> i64 {
+    let away: Duration = session.gaps.iter().map(|gap| gap.end - gap.start).sum();
+    (now - session.start_time - away).num_seconds()
+}
+
+/// Whether two creation times may belong to the same process; an unknown
+/// one cannot tell them apart.
+fn same_start(known: Option<DateTime<Local>>, seen: Option<DateTime<Local>>) -> bool {
+    known.zip(seen).is_none_or(|(known, seen)| known == seen)
+}
+
+/// The game `process` runs: one listing its executable, or else the one
+/// installed where it lives.
+fn find_game<'a>(library: &'a GameLibrary, process: &ProcessInfo) -> Option<&'a DetectedGame> {
+    let exe_name = process.windows_exe.as_deref().unwrap_or(&process.name);
+    library
+        .find_by_path(exe_name, process.exe_path.as_deref())
+        .or_else(|| library.find_by_install_dir(process.exe_path.as_deref()?))
+}
+
+fn is_launch_helper(process: &ProcessInfo) -> bool {
+    match &process.windows_exe {
+        Some(exe) => WINE_SYSTEM_EXES.iter().any(|&system_exe| exe.eq_ignore_ascii_case(system_exe)),
+        None => LAUNCH_HELPER_PROCESSES.iter().any(|&helper| process.name == helper),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use crate::models::DetectionConfidence;
+    use crate::system::{ProcessSource, ScriptedProcessSource};
+
+    fn process(name: &str, pid: u32, steam_app_id: Option<u32>) -> ProcessInfo {
+        ProcessInfo { name: name.to_string(), pid, steam_app_id, ..Default::default() }
+    }
+
+    fn witcher() -> DetectedGame {
+        DetectedGame {
+            source: "gog".to_string(),
+            store_id: Some("1207664663".to_string()),
+            title: "The Witcher 3".to_string(),
+            install_dir: None,
+            executables: vec![PathBuf::from("/games/witcher3/bin/witcher3.exe")],
+            confidence: DetectionConfidence::High,
+        }
+    }
+
+    #[test]
+    fn tracks_sessions_across_process_snapshots() {
+        let source = ScriptedProcessSource::new(vec![
+            vec![process("explorer.exe", 1, None)],
+            vec![process("explorer.exe", 1, None), process("witcher3.exe", 42, None)],
+            vec![process("reaper", 50, Some(1245620)), process("eldenring", 51, Some(1245620)), process("witcher3.exe", 42, None)],
+            vec![process("explorer.exe", 1, None)],
+        ]);
+        let mut tracker = SessionTracker::new(vec![witcher()]);
+
+        assert!(tracker.update(&source.processes().unwrap()).is_empty());
+
+        let sessions = tracker.update(&source.processes().unwrap());
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[&SessionKey::Process(ProcessId { pid: 42, start_time: None })].game_name, "The Witcher 3");
+
+        let sessions = tracker.update(&source.processes().unwrap());
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[&SessionKey::SteamApp(1245620)].process_id, 51);
+
+        assert!(tracker.update(&source.processes().unwrap()).is_empty());
+        assert!(source.processes().unwrap().is_empty());
+    }
+
+    #[test]
+    fn applies_start_and_exit_events() {
+        let mut tracker = SessionTracker::new(vec![witcher()]);
+
+        tracker.handle_event(&ProcessEvent::Snapshot(vec![process("explorer.exe", 1, None)]));
+        tracker.handle_event(&ProcessEvent::Started(process("witcher3.exe", 42, None)));
+        tracker.handle_event(&ProcessEvent::Started(process("reaper", 50, Some(1245620))));
+        tracker.handle_event(&ProcessEvent::Started(process("eldenring", 51, Some(1245620))));
+        tracker.handle_event(&ProcessEvent::Started(process("eldenring", 52, Some(1245620))));
+        assert_eq!(tracker.refresh().len(), 2);
+
+        tracker.handle_event(&ProcessEvent::Exited(ProcessId { pid: 42, start_time: None }));
+        tracker.handle_event(&ProcessEvent::Exited(ProcessId { pid: 51, start_time: None }));
+        let sessions = tracker.refresh();
+        assert_eq!(sessions.len(), 1);
+        assert!(sessions.contains_key(&SessionKey::SteamApp(1245620)));
+
+        tracker.handle_event(&ProcessEvent::Exited(ProcessId { pid: 52, start_time: None }));
+        assert!(tracker.refresh().is_empty());
+    }
+
+    #[test]
+    fn groups_a_games_process_tree_into_one_session() {
+        let game = DetectedGame {
+            source: "epic".to_string(),
+            store_id: Some("Hydra".to_string()),
+            title: "Hydra".to_string(),
+            install_dir: Some(PathBuf::from("/games/Hydra")),
+            executables: vec![PathBuf::from("/games/Hydra/Hydra.exe")],
+            confidence: DetectionConfidence::High,
+        };
+        let child = |name: &str, pid: u32, parent: Option<u32>, exe: &str| ProcessInfo {
+            name: name.to_string(),
+            pid,
+            parent_pid: parent,
+            exe_path: Some(PathBuf::from(exe)),
+            ..Default::default()
+        };
+        let bootstrapper = child("Hydra.exe", 10, Some(1), "/games/Hydra/Hydra.exe");
+        let shipping = child("Hydra-Win64-Shipping.exe", 11, Some(10), "/games/Hydra/Binaries/Win64/Hydra-Win64-Shipping.exe");
+        let crash_reporter = child("CrashReportClient.exe", 12, Some(11), "/engine/CrashReportClient.exe");
+        let mut tracker = SessionTracker::new(vec![game]);
+
+        // Children listed before their parents still join its session.
+        let sessions = tracker.update(&[crash_reporter.clone(), shipping.clone(), bootstrapper]);
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[&SessionKey::Process(ProcessId { pid: 10, start_time: None })].process_id, 10);
+
+        let sessions = tracker.update(&[crash_reporter.clone(), shipping]);
+        assert_eq!(sessions.len(), 1);
+        assert!([11, 12].contains(&sessions[&SessionKey::Process(ProcessId { pid: 10, start_time: None })].process_id));
+
+        assert_eq!(tracker.update(&[crash_reporter]).len(), 1);
+        assert!(tracker.update(&[]).is_empty());
+
+        // A game started again from its install folder alone is recognized.
+        let relaunched = child("Hydra-Win64-Shipping.exe", 20, None, "/games/Hydra/Binaries/Win64/Hydra-Win64-Shipping.exe");
+        let sessions = tracker.update(&[relaunched]);
+        assert_eq!(sessions[&SessionKey::Process(ProcessId { pid: 20, start_time: None })].game_name, "Hydra");
+    }
+
+    #[test]
+    fn launch_helpers_do_not_keep_sessions_going() {
+        let mut tracker = SessionTracker::new(vec![witcher()]);
+        let terminal = ProcessInfo { parent_pid: Some(42), ..process("bash", 43, None) };
+
+        assert_eq!(tracker.update(&[process("witcher3.exe", 42, None), terminal.clone()]).len(), 1);
+        assert!(tracker.update(std::slice::from_ref(&terminal)).is_empty());
+
+        // The helper is still in the session, so a game it starts resumes it.
+        let relaunched = ProcessInfo { parent_pid: Some(43), ..process("witcher3.exe", 44, None) };
+        let sessions = tracker.update(&[terminal, relaunched]);
+        assert_eq!(sessions[&SessionKey::Process(ProcessId { pid: 42, start_time: None })].process_id, 44);
+        assert!(matches!(&tracker.take_events()[..], [SessionEvent::Started(_), SessionEvent::Resumed(_)]));
+    }
+
+    #[test]
+    fn commands_run_by_helpers_after_the_game_exits_do_not_resume_it() {
+        let mut tracker = SessionTracker::new(vec![witcher()]);
+        let wrapper = ProcessInfo { parent_pid: Some(42), ..process("bash", 43, None) };
+        tracker.update(&[process("witcher3.exe", 42, None), wrapper.clone()]);
+        tracker.update(std::slice::from_ref(&wrapper));
+
+        let sleep = ProcessInfo { parent_pid: Some(43), exe_path: Some(PathBuf::from("/usr/bin/sleep")), ..process("sleep", 44, None) };
+        assert!(tracker.update(&[wrapper.clone(), sleep]).is_empty());
+        assert!(matches!(&tracker.take_events()[..], [SessionEvent::Started(_)]));
+
+        // Once the session ends, the wrapper leaves it, and a game it starts
+        // gets a session of its own.
+        tracker.set_grace_period(Duration::zero());
+        tracker.refresh();
+        let relaunched = ProcessInfo { parent_pid: Some(43), ..process("witcher3.exe", 45, None) };
+        let sessions = tracker.update(&[wrapper, relaunched]);
+        assert!(sessions.contains_key(&SessionKey::Process(ProcessId { pid: 45, start_time: None })));
+        assert!(matches!(&tracker.take_events()[..], [SessionEvent::Ended(_), SessionEvent::Started(_)]));
+    }
+
+    #[test]
+    fn starts_sessions_at_process_creation_and_ends_them_on_pid_reuse() {
+        let launched = Local::now() - chrono::Duration::minutes(30);
+        let witcher_at = |start_time| ProcessInfo { start_time: Some(start_time), ..process("witcher3.exe", 42, None) };
+        let mut tracker = SessionTracker::new(vec![witcher()]);
+        tracker.set_grace_period(Duration::zero());
+
+        // Already running when tracking began.
+        let first = SessionKey::Process(ProcessId { pid: 42, start_time: Some(launched) });
+        let sessions = tracker.update(&[witcher_at(launched)]);
+        assert_eq!(sessions[&first].start_time, launched);
+        assert_eq!(sessions[&first].start_time_source, StartTimeSource::ProcessCreation);
+        assert!(sessions[&first].duration_seconds >= 30 * 60);
+
+        // The game quit and the OS handed its pid to a relaunch in between.
+        let relaunched = Local::now();
+        let sessions = tracker.update(&[witcher_at(relaunched)]);
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[&SessionKey::Process(ProcessId { pid: 42, start_time: Some(relaunched) })].start_time, relaunched);
+
+        // A late exit notification for the old process is ignored.
+        tracker.handle_event(&ProcessEvent::Exited(ProcessId { pid: 42, start_time: Some(launched) }));
+        assert_eq!(tracker.refresh().len(), 1);
+
+        assert!(tracker.update(&[]).is_empty());
+        let sessions = tracker.update(&[process("witcher3.exe", 7, None)]);
+        let session = &sessions[&SessionKey::Process(ProcessId { pid: 7, start_time: None })];
+        assert_eq!(session.start_time_source, StartTimeSource::FirstSeen);
+    }
+
+    #[test]
+    fn resumes_sessions_of_games_restarted_within_their_grace_period() {
+        let crashed_at = Local::now();
+        let clock = Rc::new(Cell::new(crashed_at));
+        let mut tracker = SessionTracker::new(vec![witcher()]).with_clock({
+            let clock = clock.clone();
+            move || clock.get()
         });
+        let witcher_at = |pid, start_time| ProcessInfo { start_time: Some(start_time), ..process("witcher3.exe", pid, None) };
 
-        &self.active_sessions
+        tracker.update(&[witcher_at(42, crashed_at - Duration::seconds(40))]);
+        assert!(tracker.update(&[]).is_empty());
+        assert!(matches!(&tracker.take_events()[..], [SessionEvent::Started(_)]));
+
+        let relaunched_at = crashed_at + Duration::seconds(20);
+        clock.set(relaunched_at);
+        tracker.update(&[witcher_at(43, relaunched_at)]);
+        let events = tracker.take_events();
+        let [SessionEvent::Resumed(resumed)] = &events[..] else {
+            panic!("expected the session to resume, got {:?}", events);
+        };
+        assert_eq!(resumed.process_id, 43);
+        assert_eq!(resumed.gaps, vec![SessionGap { start: crashed_at, end: relaunched_at }]);
+
+        let quit_at = relaunched_at + Duration::seconds(10);
+        clock.set(quit_at);
+        tracker.update(&[]);
+        clock.set(quit_at + Duration::seconds(DEFAULT_GRACE_PERIOD_SECONDS - 1));
+        tracker.refresh();
+        assert!(tracker.take_events().is_empty());
+
+        clock.set(quit_at + Duration::seconds(DEFAULT_GRACE_PERIOD_SECONDS));
+        tracker.refresh();
+        let events = tracker.take_events();
+        let [SessionEvent::Ended(ended)] = &events[..] else {
+            panic!("expected the session to end, got {:?}", events);
+        };
+        assert!(!ended.is_active);
+        // 40 seconds before the crash and 10 after the relaunch.
+        assert_eq!(ended.duration_seconds, 50);
+    }
+
+    #[test]
+    fn resumes_only_sessions_of_the_same_library_game() {
+        let mut tracker = SessionTracker::new(vec![witcher()]);
+
+        // Unknown to the library, so nothing says the relaunch is the same game.
+        tracker.update(&[process("tool", 42, Some(999))]);
+        tracker.update(&[]);
+        tracker.update(&[process("tool", 43, Some(999))]);
+        assert!(matches!(&tracker.take_events()[..], [SessionEvent::Started(_), SessionEvent::Started(_)]));
+    }
+
+    #[test]
+    fn overrides_grace_periods_by_store_id_or_executable() {
+        for game in ["1207664663", "WITCHER3.EXE"] {
+            let mut tracker = SessionTracker::new(vec![witcher()]);
+            tracker.set_game_grace_period(game, Duration::zero());
+            tracker.update(&[process("witcher3.exe", 42, None)]);
+            tracker.update(&[]);
+
+            let events = tracker.take_events();
+            assert!(matches!(&events[..], [SessionEvent::Started(_), SessionEvent::Ended(_)]), "{}: {:?}", game, events);
+        }
+
+        // Other games keep the default.
+        let mut tracker = SessionTracker::new(vec![witcher()]);
+        tracker.set_game_grace_period("1091500", Duration::zero());
+        tracker.update(&[process("witcher3.exe", 42, None)]);
+        tracker.update(&[]);
+        assert!(matches!(&tracker.take_events()[..], [SessionEvent::Started(_)]));
     }
-    
 }
@@ -0,0 +1,435 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use crate::cache::LibraryCache;
+use crate::detectors::context::ScanContext;
+use crate::detectors::{report_result, run_detector, wine, DetectorRegistry};
+use crate::models::DetectedGame;
+
+/// How long a detector's sources must stay quiet before it reruns.
+/// Launchers write their manifests in several steps while installing.
+const SETTLE_TIME: Duration = Duration::from_secs(2);
+
+/// How often every source is compared when inotify is not available, and
+/// sources it cannot watch are compared when it is.
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LibraryEvent {
+    Installed(DetectedGame),
+    Uninstalled(DetectedGame),
+    /// Still installed, but the launcher now reports different details,
+    /// e.g. new executables after an update.
+    Updated(DetectedGame),
+}
+
+/// What one detector found on one scanned machine or Wine prefix.
+struct WatchedScan {
+    detector: &'static str,
+    /// Index into [`LibraryWatcher::contexts`].
+    context: usize,
+    /// Detectors without sources cannot be watched and are never rerun.
+    watched: bool,
+    games: Vec<DetectedGame>,
+}
+
+/// Keeps the game library current while the tracker runs. The files each
+/// detector reads are watched, with inotify on Linux and by polling
+/// elsewhere, and only the detectors whose files changed are rerun.
+pub struct LibraryWatcher {
+    contexts: Vec<ScanContext>,
+    scans: Vec<WatchedScan>,
+    notifier: Option<Notifier>,
+    /// Scans whose sources changed since they were last checked.
+    pending: HashSet<usize>,
+    /// Scans with a source the notifier cannot watch, which are polled
+    /// even when the others are notified.
+    unwatched: HashSet<usize>,
+    last_change: Instant,
+    last_poll: Instant,
+    settle_time: Duration,
+    poll_interval: Duration,
+}
+
+impl LibraryWatcher {
+    /// Runs the detectors on `context` and, on Linux, inside every Wine
+    /// prefix found there, and starts watching their sources.
+    pub fn scan(registry: &DetectorRegistry, context: ScanContext, cache: &mut LibraryCache) -> Self {
+        println!("1. Preparing game list...");
+
+        let mut contexts = wine::prefix_contexts(&context);
+        contexts.insert(0, context);
+
+        let mut watcher = Self {
+            contexts,
+            scans: Vec::new(),
+            notifier: Notifier::new(),
+            pending: HashSet::new(),
+            unwatched: HashSet::new(),
+            last_change: Instant::now(),
+            last_poll: Instant::now(),
+            settle_time: SETTLE_TIME,
+            poll_interval: POLL_INTERVAL,
+        };
+
+        for (index, context) in watcher.contexts.iter().enumerate() {
+            for result in registry.run(context, cache) {
+                let sources = registry.get(result.id).map(|d| d.sources(context)).unwrap_or_default();
+                if let Some(notifier) = &mut watcher.notifier
+                    && !notifier.watch(watcher.scans.len(), &sources)
+                {
+                    watcher.unwatched.insert(watcher.scans.len());
+                }
+                watcher.scans.push(WatchedScan {
+                    detector: result.id,
+                    context: index,
+                    watched: !sources.is_empty(),
+                    games: report_result(result),
+                });
+            }
+        }
+
+        watcher
+    }
+
+    /// Every installed game, in detector order.
+    pub fn games(&self) -> Vec<DetectedGame> {
+        self.scans.iter().flat_map(|scan| scan.games.iter().cloned()).collect()
+    }
+
+    /// Reruns the detectors whose sources changed and settled, and returns
+    /// how the library changed. Cheap when nothing happened, so it can run
+    /// on every tracker tick.
+    pub fn poll(&mut self, registry: &DetectorRegistry, cache: &mut LibraryCache) -> Vec<LibraryEvent> {
+        let now = Instant::now();
+        let poll_due = now.duration_since(self.last_poll) >= self.poll_interval;
+        if poll_due {
+            self.last_poll = now;
+        }
+
+        match &mut self.notifier {
+            Some(notifier) => {
+                let changed = notifier.changed_scans();
+                if !changed.is_empty() {
+                    self.pending.extend(changed);
+                    self.last_change = now;
+                }
+                if poll_due {
+                    self.pending.extend(&self.unwatched);
+                }
+            }
+            None if poll_due => {
+                self.pending.extend((0..self.scans.len()).filter(|&index| self.scans[index].watched));
+            }
+            None => {}
+        }
+
+        if self.pending.is_empty() || now.duration_since(self.last_change) < self.settle_time {
+            return Vec::new();
+        }
+
+        let mut pending: Vec<usize> = self.pending.drain().collect();
+        pending.sort_unstable();
+        pending.into_iter().flat_map(|index| self.refresh(index, registry, cache)).collect()
+    }
+
+    /// Reruns one scan's detector unless its sources still match the cache.
+    fn refresh(&mut self, index: usize, registry: &DetectorRegistry, cache: &mut LibraryCache) -> Vec<LibraryEvent> {
+        let scan = &mut self.scans[index];
+        let context = &self.contexts[scan.context];
+        let Some(detector) = registry.get(scan.detector) else {
+            return Vec::new();
+        };
+
+        // Sources can move, e.g. when a Steam library folder is added.
+        if let Some(notifier) = &mut self.notifier {
+            if notifier.watch(index, &detector.sources(context)) {
+                self.unwatched.remove(&index);
+            } else {
+                self.unwatched.insert(index);
+            }
+        }
+
+        let result = run_detector(detector, context, cache);
+        if result.cached {
+            return Vec::new();
+        }
+        let games = match result.games {
+            Ok(games) => games,
+            Err(e) => {
+                eprintln!("⚠️ {} detector ({}) failed: {}", result.display_name, result.id, e);
+                return Vec::new();
+            }
+        };
+
+        let events = diff_games(&scan.games, &games);
+        for event in &events {
+            match event {
+                LibraryEvent::Installed(game) => println!("📥 GAME INSTALLED: {} ({})", game.title, game.source),
+                LibraryEvent::Uninstalled(game) => println!("🗑️ GAME UNINSTALLED: {} ({})", game.title, game.source),
+                LibraryEvent::Updated(game) => println!("🔄 GAME UPDATED: {} ({})", game.title, game.source),
+            }
+        }
+        scan.games = games;
+        events
+    }
+}
+
+/// Games are matched by launcher and store id, or by title for detectors
+/// that have no ids.
+fn diff_games(old: &[DetectedGame], new: &[DetectedGame]) -> Vec<LibraryEvent> {
+    let same_game = |a: &DetectedGame, b: &DetectedGame| {
+        a.source == b.source && a.store_id == b.store_id && (a.store_id.is_some() || a.title == b.title)
+    };
+
+    let mut events: Vec<LibraryEvent> = old
+        .iter()
+        .filter(|game| !new.iter().any(|n| same_game(game, n)))
+        .map(|game| LibraryEvent::Uninstalled(game.clone()))
+        .collect();
+
+    for game in new {
+        match old.iter().find(|o| same_game(o, game)) {
+            None => events.push(LibraryEvent::Installed(game.clone())),
+            Some(previous) if previous != game => events.push(LibraryEvent::Updated(game.clone())),
+            Some(_) => {}
+        }
+    }
+
+    events
+}
+
+/// Reports which scans' source folders saw changes.
+#[cfg(target_os = "linux")]
+struct Notifier {
+    inotify: inotify::Inotify,
+    watches: std::collections::HashMap<inotify::WatchDescriptor, HashSet<usize>>,
+}
+
+#[cfg(target_os = "linux")]
+impl Notifier {
+    fn new() -> Option<Self> {
+        match inotify::Inotify::init() {
+            Ok(inotify) => Some(Self { inotify, watches: Default::default() }),
+            Err(e) => {
+                eprintln!("⚠️ inotify is unavailable, polling launcher files instead: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Watches the folders holding `sources` for scan `index`. Sources that
+    /// do not exist yet are watched through their parent folder, so creating
+    /// them is noticed too. Returns false if that folder is missing as well:
+    /// climbing further could end up watching all of `$HOME`, so the scan
+    /// is polled instead.
+    fn watch(&mut self, index: usize, sources: &[PathBuf]) -> bool {
+        use inotify::WatchMask;
+
+        let mask = WatchMask::CREATE
+            | WatchMask::DELETE
+            | WatchMask::MODIFY
+            | WatchMask::CLOSE_WRITE
+            | WatchMask::MOVED_FROM
+            | WatchMask::MOVED_TO
+            | WatchMask::DELETE_SELF
+            | WatchMask::MOVE_SELF;
+
+        let mut watched_all = true;
+        for source in sources {
+            let Some(dir) = source.ancestors().take(2).find(|path| path.is_dir()) else {
+                watched_all = false;
+                continue;
+            };
+            match self.inotify.watches().add(dir, mask) {
+                Ok(wd) => {
+                    self.watches.entry(wd).or_default().insert(index);
+                }
+                Err(e) => {
+                    eprintln!("⚠️ Cannot watch {}: {}", dir.display(), e);
+                    watched_all = false;
+                }
+            }
+        }
+        watched_all
+    }
+
+    fn changed_scans(&mut self) -> HashSet<usize> {
+        let mut buffer = [0; 4096];
+        let mut changed = HashSet::new();
+
+        loop {
+            match self.inotify.read_events(&mut buffer) {
+                Ok(events) => {
+                    let mut read_any = false;
+                    for event in events {
+                        read_any = true;
+                        if let Some(scans) = self.watches.get(&event.wd) {
+                            changed.extend(scans);
+                        }
+                    }
+                    if !read_any {
+                        break;
+                    }
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
+                Err(e) => {
+                    eprintln!("⚠️ Failed to read inotify events: {}", e);
+                    break;
+                }
+            }
+        }
+
+        changed
+    }
+}
+
+/// Without inotify every watched source is compared on each poll interval.
+#[cfg(not(target_os = "linux"))]
+struct Notifier;
+
+#[cfg(not(target_os = "linux"))]
+impl Notifier {
+    fn new() -> Option<Self> {
+        None
+    }
+
+    fn watch(&mut self, _index: usize, _sources: &[PathBuf]) -> bool {
+        false
+    }
+
+    fn changed_scans(&mut self) -> HashSet<usize> {
+        HashSet::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detectors::{GameDetector, Platform};
+    use crate::models::DetectionConfidence;
+
+    /// Reports every `.exe` in a folder as a game.
+    struct FolderDetector {
+        dir: PathBuf,
+    }
+
+    impl GameDetector for FolderDetector {
+        fn id(&self) -> &'static str {
+            "folder"
+        }
+
+        fn display_name(&self) -> &'static str {
+            "Folder"
+        }
+
+        fn platforms(&self) -> &'static [Platform] {
+            &[Platform::Windows, Platform::Linux, Platform::MacOs]
+        }
+
+        fn sources(&self, _context: &ScanContext) -> Vec<PathBuf> {
+            vec![self.dir.clone()]
+        }
+
+        fn discover(&self, _context: &ScanContext) -> Result<Vec<DetectedGame>, Box<dyn std::error::Error>> {
+            let mut games: Vec<DetectedGame> = std::fs::read_dir(&self.dir)?
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "exe"))
+                .map(|path| DetectedGame {
+                    source: "folder".to_string(),
+                    store_id: None,
+                    title: path.file_stem().unwrap().to_string_lossy().to_string(),
+                    install_dir: Some(self.dir.clone()),
+                    executables: vec![path],
+                    confidence: DetectionConfidence::High,
+                })
+                .collect();
+            games.sort_by(|a, b| a.title.cmp(&b.title));
+            Ok(games)
+        }
+    }
+
+    fn poll_until_changed(watcher: &mut LibraryWatcher, registry: &DetectorRegistry, cache: &mut LibraryCache) -> Vec<LibraryEvent> {
+        // inotify events and mtimes can lag the write slightly.
+        for _ in 0..50 {
+            let events = watcher.poll(registry, cache);
+            if !events.is_empty() {
+                return events;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        Vec::new()
+    }
+
+    #[test]
+    fn reruns_detectors_when_their_sources_change() {
+        let dir = std::env::temp_dir().join(format!("library_watcher_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("Celeste.exe"), "").unwrap();
+
+        let mut registry = DetectorRegistry::new();
+        registry.register(Box::new(FolderDetector { dir: dir.clone() }));
+        let mut cache = LibraryCache::default();
+        let mut watcher = LibraryWatcher::scan(&registry, ScanContext::offline(Platform::Windows, &dir), &mut cache);
+        watcher.settle_time = Duration::ZERO;
+        watcher.poll_interval = Duration::ZERO;
+        assert_eq!(watcher.games().len(), 1);
+        assert!(watcher.poll(&registry, &mut cache).is_empty());
+
+        std::fs::write(dir.join("Hades.exe"), "").unwrap();
+        let events = poll_until_changed(&mut watcher, &registry, &mut cache);
+        assert!(matches!(&events[..], [LibraryEvent::Installed(game)] if game.title == "Hades"));
+        let installed: Vec<String> = watcher.games().into_iter().map(|g| g.title).collect();
+        assert_eq!(installed, vec!["Celeste", "Hades"]);
+
+        std::fs::remove_file(dir.join("Celeste.exe")).unwrap();
+        let events = poll_until_changed(&mut watcher, &registry, &mut cache);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(matches!(&events[..], [LibraryEvent::Uninstalled(game)] if game.title == "Celeste"));
+        assert_eq!(watcher.games().len(), 1);
+    }
+
+    #[test]
+    fn polls_sources_whose_parent_folder_is_missing() {
+        let root = std::env::temp_dir().join(format!("library_watcher_missing_{}", std::process::id()));
+        let dir = root.join("launcher").join("games");
+        std::fs::create_dir_all(&root).unwrap();
+
+        let mut registry = DetectorRegistry::new();
+        registry.register(Box::new(FolderDetector { dir: dir.clone() }));
+        let mut cache = LibraryCache::default();
+        let mut watcher = LibraryWatcher::scan(&registry, ScanContext::offline(Platform::Windows, &root), &mut cache);
+        watcher.settle_time = Duration::ZERO;
+        watcher.poll_interval = Duration::ZERO;
+        if watcher.notifier.is_some() {
+            assert_eq!(watcher.unwatched, HashSet::from([0]));
+        }
+
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("Celeste.exe"), "").unwrap();
+        let events = poll_until_changed(&mut watcher, &registry, &mut cache);
+        let still_unwatched = watcher.unwatched.contains(&0);
+        std::fs::remove_dir_all(&root).unwrap();
+        assert!(matches!(&events[..], [LibraryEvent::Installed(game)] if game.title == "Celeste"));
+        assert!(!still_unwatched);
+    }
+
+    #[test]
+    fn matches_games_by_store_id_before_title() {
+        let game = |id: &str, title: &str| DetectedGame {
+            source: "steam".to_string(),
+            store_id: Some(id.to_string()),
+            title: title.to_string(),
+            install_dir: None,
+            executables: Vec::new(),
+            confidence: DetectionConfidence::High,
+        };
+
+        let events = diff_games(&[game("70", "Half-Life"), game("220", "Half-Life 2")], &[game("70", "Half-Life: Source")]);
+        assert_eq!(
+            events,
+            vec![LibraryEvent::Uninstalled(game("220", "Half-Life 2")), LibraryEvent::Updated(game("70", "Half-Life: Source"))]
+        );
+    }
+}
@@ -0,0 +1,108 @@
+"""Writes the synthetic SOFTWARE hive used by the registry tests.
+
+Not taken from a Windows install: every key, value and path is made up.
+It covers each subkey list kind (lf, lh, li and an ri index over two li
+lists), Latin-1 and UTF-16 names, and a value split into db segments.
+
+    python3 tests/fixtures/registry/mkhive.py > tests/fixtures/registry/SOFTWARE
+"""
+import struct, sys
+
+cells = bytearray()
+def alloc(data):
+    global cells
+    data = bytes(data)
+    size = 4 + len(data)
+    size = (size + 7) & ~7
+    off = 32 + len(cells)  # relative to hbin start (hbin header is 32 bytes)
+    cells += struct.pack('<i', -size) + data + b'\0' * (size - 4 - len(data))
+    return off
+
+def vk(name, vtype, data, utf16_name=False):
+    nameb = name.encode('utf-16-le') if utf16_name else name.encode('latin-1')
+    flags = 0 if utf16_name else 1
+    if len(data) <= 4:
+        size = len(data) | 0x80000000
+        doff = struct.unpack('<I', data.ljust(4, b'\0'))[0]
+    elif len(data) > 16344:
+        segs = []
+        for i in range(0, len(data), 16344):
+            segs.append(alloc(data[i:i+16344]))
+        lst = alloc(b''.join(struct.pack('<I', s) for s in segs))
+        doff = alloc(b'db' + struct.pack('<HI', len(segs), lst))
+        size = len(data)
+    else:
+        doff = alloc(data); size = len(data)
+    return alloc(b'vk' + struct.pack('<HIIIHH', len(nameb), size, doff, vtype, flags, 0) + nameb)
+
+def sz(s): return (s + '\0').encode('utf-16-le')
+
+def nk(name, subkey_list, nsub, values, utf16_name=False, root=False):
+    nameb = name.encode('utf-16-le') if utf16_name else name.encode('latin-1')
+    flags = (0 if utf16_name else 0x20) | (0x4 if root else 0)
+    vlist = alloc(b''.join(struct.pack('<I', v) for v in values)) if values else 0xffffffff
+    body = b'nk' + struct.pack('<H', flags) + b'\0' * 8 + struct.pack('<I', 0) + struct.pack('<I', 0)
+    body += struct.pack('<IIIIII', nsub, 0, subkey_list, 0xffffffff, len(values), vlist)
+    body += struct.pack('<II', 0xffffffff, 0xffffffff) + b'\0' * 20
+    body += struct.pack('<HH', len(nameb), 0) + nameb
+    assert body[72:74] == struct.pack('<H', len(nameb))
+    return alloc(body)
+
+def lf(children, sig=b'lf'):
+    return alloc(sig + struct.pack('<H', len(children)) + b''.join(struct.pack('<II', c, 0) for c in children))
+
+def li(children):
+    return alloc(b'li' + struct.pack('<H', len(children)) + b''.join(struct.pack('<I', c) for c in children))
+
+def ri(lists):
+    return alloc(b'ri' + struct.pack('<H', len(lists)) + b''.join(struct.pack('<I', c) for c in lists))
+
+def key(name, children=(), values=(), list_kind='lf', **kw):
+    children = list(children)
+    if not children:
+        return nk(name, 0xffffffff, 0, list(values), **kw)
+    if list_kind == 'ri':
+        lst = ri([li(children[:1]), li(children[1:])])
+    elif list_kind == 'li':
+        lst = li(children)
+    else:
+        lst = lf(children, list_kind.encode())
+    return nk(name, lst, len(children), list(values), **kw)
+
+drive = 'C:'
+hk = key('Hollow Knight_is1', values=[
+    vk('', 1, sz('default')),
+    vk('DisplayName', 1, sz('Hollow Knight')),
+    vk('Publisher', 1, sz('Team Cherry')),
+    vk('InstallLocation', 1, sz(drive + '\\Games\\Hollow Knight')),
+    vk('DisplayIcon', 1, sz(drive + '\\Games\\Hollow Knight\\hollow_knight.exe')),
+    vk('EstimatedSize', 4, struct.pack('<I', 9437184)),
+    vk('Notes', 3, bytes([0xab]) * 20000),
+])
+zip_ = key('{7-Zip}', values=[
+    vk('DisplayName', 1, sz('7-Zip 23.01 (x64)')),
+    vk('Publisher', 1, sz('Igor Pavlov')),
+    vk('SystemComponent', 4, struct.pack('<I', 0)),
+], utf16_name=True)
+uninstall = key('Uninstall', [hk, zip_], list_kind='lh')
+cv = key('CurrentVersion', [uninstall])
+win = key('Windows', [cv])
+ms = key('Microsoft', [win])
+fc6 = key('5266', values=[vk('InstallDir', 1, sz('D:\\Games\\Far Cry 6\\'))])
+ac = key('4311', values=[vk('InstallDir', 1, sz('D:\\Games\\Assassin\'s Creed Odyssey\\'))])
+installs = key('Installs', [fc6, ac], list_kind='ri')
+launcher = key('Launcher', [installs], list_kind='li')
+ubi = key('Ubisoft', [launcher])
+wow = key('WOW6432Node', [ubi])
+root = key('CsiTool-CreateHive-{00000000-0000-0000-0000-000000000000}', [ms, wow], root=True)
+
+hbin_size = (32 + len(cells) + 0xfff) & ~0xfff
+pad = hbin_size - 32 - len(cells)
+cells += struct.pack('<i', pad) + b'\0' * (pad - 4)  # free cell filling the bin
+hbin = b'hbin' + struct.pack('<III', 0, hbin_size, 0) + b'\0' * 16 + cells
+base = bytearray(4096)
+base[0:4] = b'regf'
+struct.pack_into('<IIQIIIII', base, 4, 1, 1, 0, 1, 6, 0, 1, root)
+struct.pack_into('<I', base, 40, hbin_size)
+struct.pack_into('<I', base, 44, 1)
+sys.stdout.buffer.write(bytes(base) + hbin)